@@ -71,6 +71,12 @@ async fn main() -> io::Result<()> {
         // Render the lines current state
         repl.render(&mut command_ctx)?;
 
+        // Submit the lines of a confirmed multi-line paste before awaiting new events
+        if let Some(queued) = repl.process_queued_input()? {
+            general_event_process!(&mut repl, &mut command_ctx, queued: queued);
+            continue;
+        }
+
         // Process async events as they happen
         tokio::select! {
             biased;
@@ -2,15 +2,14 @@
 /*         cargo r --example spawner --features="spawner"          */
 
 use repl_oxide::{
-    ansi_code::{GREEN, RED, RESET},
     clap::try_parse_from,
     executor::{CommandHandle, Executor},
-    Repl,
+    Level, LogMessage, Repl,
 };
 
 use std::{
-    fmt::Display,
     io::{self, Stdout},
+    path::PathBuf,
 };
 
 use clap::Parser;
@@ -25,6 +24,16 @@ use tokio::{
     about = "Example app demonstrating repl-oxide's spawner feature"
 )]
 enum Command {
+    /// Set the minimum level of displayed background messages
+    Loglevel {
+        /// One of: trace, debug, info, warn, error
+        level: Level,
+    },
+    /// Start recording a transcript of the session, stops recording if no path is given
+    Record {
+        /// File the transcript is appended to
+        path: Option<PathBuf>,
+    },
     /// Exit the command line REPL
     #[command(alias = "exit")]
     Quit,
@@ -42,6 +51,20 @@ impl Executor<Stdout> for CommandContext {
     ) -> io::Result<CommandHandle<Self, Stdout>> {
         match try_parse_from(&user_tokens) {
             Ok(command) => match command {
+                Command::Loglevel { level } => {
+                    repl_handle.set_log_level(level);
+                    Ok(CommandHandle::Processed)
+                }
+                Command::Record { path } => {
+                    let recorded = match path {
+                        Some(path) => repl_handle.start_transcript(path, true),
+                        None => repl_handle.stop_transcript(),
+                    };
+                    if let Err(err) = recorded {
+                        repl_handle.eprintln(format!("Could not record transcript: {err}"))?;
+                    }
+                    Ok(CommandHandle::Processed)
+                }
                 Command::Quit => Ok(CommandHandle::Exit),
             },
             Err(err) => repl_handle.print_clap_err(err),
@@ -49,30 +72,16 @@ impl Executor<Stdout> for CommandContext {
     }
 }
 
-// Our message type to send to the repl If we need to display text to the user outside of
-// the `CommandContext` scope
-enum Message {
-    Err(String),
-    Info(String),
-}
-
-// We must impl `Display` for our `Message` type so the repl knows how to display the text
-// Note the repl loop will take care of appending a new line character
-impl Display for Message {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let (line_color, msg): (&str, &str) = match self {
-            Message::Info(msg) => (GREEN, msg),
-            Message::Err(msg) => (RED, msg),
-        };
-        write!(f, "{line_color}{msg}{RESET}")
-    }
-}
-
-fn print_timer(sender: Sender<Message>) -> tokio::task::JoinHandle<()> {
+// Any type that implements `Display` can be sent to the repl, `LogMessage`s are displayed with a level
+// that can be filtered at runtime. Note the repl loop will take care of appending a new line character
+fn print_timer(sender: Sender<LogMessage>) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         loop {
             sleep(Duration::from_secs(2)).await;
-            if sender.send(Message::Info("Timer".into())).await.is_err() {
+            let msg = LogMessage::debug("Timer")
+                .with_source("timer")
+                .with_timestamp();
+            if sender.send(msg).await.is_err() {
                 break;
             };
         }
@@ -88,6 +97,7 @@ async fn check_for_update() -> Result<(), &'static str> {
 async fn main() -> io::Result<()> {
     // Spawn repl on the current runtime
     let (repl_task, message_sender) = Repl::new(io::stdout())
+        .with_log_level(Level::Debug)
         .build()
         .expect("input writer accepts crossterm commands")
         .spawn(CommandContext);
@@ -97,7 +107,8 @@ async fn main() -> io::Result<()> {
 
     // Simulate some async tcp request
     if let Err(err) = check_for_update().await {
-        let _ = message_sender.send(Message::Err(err.into())).await;
+        let msg = LogMessage::error(err).with_source("update");
+        let _ = message_sender.send(msg).await;
     }
 
     // Await repl to finish
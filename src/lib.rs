@@ -24,6 +24,12 @@ pub mod runner;
 #[doc(hidden)]
 pub mod spawner;
 
+#[cfg(feature = "tracing-layer")]
+mod tracing_layer;
+
+#[cfg(feature = "tracing-layer")]
+pub use tracing_layer::ReplTracingLayer;
+
 pub use line::style::ansi_code;
 pub use line::*;
 
@@ -1,12 +1,18 @@
 use crate::line::{
     completion::{CommandScheme, Completion},
     history::History,
-    LineData, ParseErrHook, Repl,
+    theme::{ColorSupport, Theme},
+    transcript::Transcript,
+    DynamicPrompt, Highlighter, IncompletePredicate, Level, LineData, ParseErrHook, Repl,
+    SharedLevel, StatusBar,
 };
 
-use std::io::{self, ErrorKind, Write};
+use std::{
+    io::{self, ErrorKind, Write},
+    path::PathBuf,
+};
 
-use crossterm::{cursor, terminal, QueueableCommand};
+use crossterm::{cursor, event::EnableBracketedPaste, terminal, QueueableCommand};
 use shellwords::split as shellwords_split;
 
 /* -------------------------------- Debug tool -------------------------------- */
@@ -44,9 +50,24 @@ pub struct ReplBuilder<'a, Ctx, W: Write> {
     term_size: Option<(u16, u16)>,
     prompt: Option<String>,
     prompt_end: Option<String>,
+    right_prompt: Option<String>,
+    transient_prompt: Option<String>,
     starting_history: Option<History>,
     style_enabled: bool,
+    hints_enabled: bool,
+    signature_enabled: bool,
+    block_invalid: bool,
+    vars_enabled: bool,
+    editor_submit: bool,
+    multi_line_enabled: bool,
+    incomplete_predicate: Option<Box<dyn IncompletePredicate>>,
+    highlighter: Option<Box<dyn Highlighter>>,
+    theme: Option<Theme>,
     parse_err_hook: Option<Box<dyn ParseErrHook<Ctx, W>>>,
+    dynamic_prompt: Option<Box<dyn DynamicPrompt<Ctx>>>,
+    status_bar: Option<Box<dyn StatusBar<Ctx>>>,
+    log_level: Option<Level>,
+    transcript: Option<(PathBuf, bool)>,
 }
 
 impl<Ctx, W: Write> Repl<Ctx, W> {
@@ -64,9 +85,24 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
             term_size: None,
             prompt: None,
             prompt_end: None,
+            right_prompt: None,
+            transient_prompt: None,
             starting_history: None,
             style_enabled: true,
+            hints_enabled: false,
+            signature_enabled: false,
+            block_invalid: false,
+            vars_enabled: false,
+            editor_submit: false,
+            multi_line_enabled: false,
+            incomplete_predicate: None,
+            highlighter: None,
+            theme: None,
             parse_err_hook: None,
+            dynamic_prompt: None,
+            status_bar: None,
+            log_level: None,
+            transcript: None,
         }
     }
 }
@@ -110,18 +146,148 @@ impl<Ctx, W: Write> ReplBuilder<'_, Ctx, W> {
         self
     }
 
+    /// Sets the [`Highlighter`] used to style the input line. By default tokens are styled by their role within
+    /// the [`CommandScheme`] if completion is set, otherwise [`DefaultHighlighter`] is used. Has no effect if
+    /// line stylization is disabled
+    ///
+    /// [`DefaultHighlighter`]: crate::line::DefaultHighlighter
+    pub fn with_highlighter(mut self, highlighter: impl Highlighter) -> Self {
+        self.highlighter = Some(Box::new(highlighter));
+        self
+    }
+
+    /// Sets the [`Theme`] used to style the repl, if none is supplied [`Theme::default`] is used. Colors are
+    /// downgraded to what the terminal supports, only text attributes are displayed if the `NO_COLOR`
+    /// environment variable is set. Has no effect if line stylization is disabled
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    /// Displays the reason the current input line is invalid as a dim hint under the input line. Requires
+    /// completion to be set. See: [`Repl::diagnostics`]
+    pub fn with_diagnostic_hints(mut self) -> Self {
+        self.hints_enabled = true;
+        self
+    }
+
+    /// Displays the usage of the current command as a dim hint under the input line, eg.
+    /// `roll [--sides <SIDES>]`, the input that is currently expected is emphasized. Requires completion to
+    /// be set
+    pub fn with_signature_hints(mut self) -> Self {
+        self.signature_enabled = true;
+        self
+    }
+
+    /// Refuses to submit input lines that contain known errors. The terminal bell is rung and the reason is
+    /// displayed as a hint under the input line. Submission can still be forced with <kbd>Alt</kbd> +
+    /// <kbd>Enter</kbd>, unless multi-line input is enabled, then <kbd>Alt</kbd> + <kbd>Enter</kbd> inserts a new
+    /// row instead. Requires completion to be set. See: [`Repl::diagnostics`], [`Self::with_multi_line_input`]
+    pub fn with_invalid_submissions_blocked(mut self) -> Self {
+        self.block_invalid = true;
+        self
+    }
+
+    /// Expands `$NAME` and `${NAME}` references within submitted lines before they are split into tokens.
+    /// Values are looked up within the repl's variables first, then the process environment. Known variable
+    /// names are suggested when completing a `$` reference if completion is enabled. See: [`Repl::set_var`]
+    pub fn with_variable_expansion(mut self) -> Self {
+        self.vars_enabled = true;
+        self
+    }
+
+    /// Pressing <kbd>Enter</kbd> on an input line that contains an unclosed quote or ends with a backslash
+    /// continues the input on a new row, displayed after a `...` continuation prompt, rather than submitting it.
+    /// <kbd>↑</kbd> and <kbd>↓</kbd> move between rows before navigating history. Line continuations are removed
+    /// from submitted lines.
+    ///
+    /// | Keys | Incomplete line | Line with errors, while [blocked] | Otherwise |
+    /// |------|-----------------|-----------------------------------|-----------|
+    /// | <kbd>Enter</kbd> | Inserts a new row | Refuses submission | Submits |
+    /// | <kbd>Alt</kbd> + <kbd>Enter</kbd> | Inserts a new row | Inserts a new row | Inserts a new row |
+    ///
+    /// Forcing the submission of a [blocked] line with <kbd>Alt</kbd> + <kbd>Enter</kbd> is only possible while
+    /// multi-line input is disabled.
+    ///
+    /// [blocked]: Self::with_invalid_submissions_blocked
+    pub fn with_multi_line_input(mut self) -> Self {
+        self.multi_line_enabled = true;
+        self
+    }
+
+    /// Supply an [`IncompletePredicate`] to mark additional input lines as incomplete, enables multi-line input.
+    /// See: [`Self::with_multi_line_input`]
+    pub fn with_incomplete_input_predicate(mut self, predicate: impl IncompletePredicate) -> Self {
+        self.incomplete_predicate = Some(Box::new(predicate));
+        self.multi_line_enabled = true;
+        self
+    }
+
+    /// Lines edited within an external editor, opened with <kbd>Ctrl</kbd> + <kbd>X</kbd>, <kbd>Ctrl</kbd> +
+    /// <kbd>E</kbd>, are submitted once the editor exits rather than placed back into the input line. See:
+    /// [`Repl::edit_in_editor`]
+    pub fn with_editor_auto_submit(mut self) -> Self {
+        self.editor_submit = true;
+        self
+    }
+
     /// Supply a default prompt the line should display, if none is supplied `'>'` is used.
     pub fn with_prompt(mut self, prompt: &str) -> Self {
         self.prompt = Some(String::from(prompt.trim()));
         self
     }
 
+    /// Supply a [`DynamicPrompt`] that computes the prompt from the repl's context on every call to
+    /// [`Repl::render`], eg. to display the number of running jobs. Takes priority over [`Self::with_prompt`]
+    pub fn with_dynamic_prompt(mut self, prompt: impl DynamicPrompt<Ctx>) -> Self {
+        self.dynamic_prompt = Some(Box::new(prompt));
+        self
+    }
+
+    /// Supply a [`StatusBar`] that is computed from the repl's context and displayed under the input line on
+    /// every call to [`Repl::render`], eg. to display the current mode or key hints
+    pub fn with_status_bar(mut self, status_bar: impl StatusBar<Ctx>) -> Self {
+        self.status_bar = Some(Box::new(status_bar));
+        self
+    }
+
+    /// Supply the minimum level of [`LogMessage`]s that are displayed, if none is supplied [`Level::Info`] is
+    /// used. See: [`Repl::set_log_level`]
+    ///
+    /// [`LogMessage`]: crate::line::LogMessage
+    pub fn with_log_level(mut self, level: Level) -> Self {
+        self.log_level = Some(level);
+        self
+    }
+
+    /// Record a transcript of the session into the file at the given `path`, set `strip_ansi` to remove all
+    /// ANSI escape codes from the recorded text. See: [`Repl::start_transcript`]
+    pub fn with_transcript<P: Into<PathBuf>>(mut self, path: P, strip_ansi: bool) -> Self {
+        self.transcript = Some((path.into(), strip_ansi));
+        self
+    }
+
     /// Supply a custom prompt separator to override the default prompt separator `'>'`.
     pub fn with_custom_prompt_separator(mut self, separator: &str) -> Self {
         self.prompt_end = Some(String::from(separator.trim()));
         self
     }
 
+    /// Supply a prompt that is displayed right aligned on the first row of the input line. The right prompt is
+    /// hidden while the input line would collide with it, see: [`Repl::set_right_prompt`]
+    pub fn with_right_prompt(mut self, prompt: &str) -> Self {
+        self.right_prompt = Some(String::from(prompt.trim()));
+        self
+    }
+
+    /// Once a line is submitted it is redrawn with the given minimal `prompt`, eg. `">"`, in place of the prompt
+    /// and prompt separator. Keeps the scrollback free of long prompts as the full prompt is only displayed on
+    /// the active line
+    pub fn with_transient_prompt(mut self, prompt: &str) -> Self {
+        self.transient_prompt = Some(String::from(prompt.trim()));
+        self
+    }
+
     /// Supply history entries that the repl should start with. The end of the given `entries` slice will
     /// be the most recent.
     pub fn with_history_entries<S: AsRef<str>>(mut self, entries: &[S]) -> Self {
@@ -146,12 +312,13 @@ impl<Ctx, W: Write> ReplBuilder<'_, Ctx, W> {
     /// - The supplied terminal writer does not accept crossterm commands
     /// - No terminal size was provided and a call to [`terminal::size`] returns `Err`
     /// - A custom quit command was supplied and the string contained mismatched quotes
-    ///
-    /// This function will panic if an ill formed [`&'static CommandScheme`] was supplied
+    /// - An ill formed [`&'static CommandScheme`] was supplied, the inner error will be [`SchemeErrors`]
+    /// - A transcript path was supplied and the file could not be opened
     ///
     /// [`run`]: crate::line::Repl::run
     /// [`spawn`]: crate::line::Repl::spawn
     /// [`&'static CommandScheme`]: crate::completion::CommandScheme
+    /// [`SchemeErrors`]: crate::completion::SchemeErrors
     /// [`terminal::size`]: <https://docs.rs/crossterm/latest/crossterm/terminal/fn.size.html>
     pub fn build(mut self) -> io::Result<Repl<Ctx, W>> {
         let term_size = match self.term_size {
@@ -170,24 +337,52 @@ impl<Ctx, W: Write> ReplBuilder<'_, Ctx, W> {
             })?),
             None => None,
         };
-        let completion = self.completion.map(Completion::from).unwrap_or_default();
+        let completion = self
+            .completion
+            .map(Completion::try_from_scheme)
+            .transpose()
+            .map_err(|errors| io::Error::new(ErrorKind::InvalidInput, errors))?
+            .unwrap_or_default();
+        let transcript = self
+            .transcript
+            .map(|(path, strip_ansi)| Transcript::open(&path, strip_ansi))
+            .transpose()?;
 
         crossterm::terminal::enable_raw_mode()?;
         self.term.queue(cursor::EnableBlinking)?;
+        // Legacy Windows consoles do not support bracketed paste, pastes are then received as key events
+        let _ = self.term.queue(EnableBracketedPaste);
 
-        Ok(Repl::from(
-            LineData::new(
-                self.prompt,
-                self.prompt_end,
-                self.style_enabled,
-                !completion.is_empty(),
-            ),
+        let mut repl = Repl::from(
+            LineData {
+                style_enabled: self.style_enabled,
+                hints_enabled: self.hints_enabled,
+                signature_enabled: self.signature_enabled,
+                block_invalid: self.block_invalid,
+                vars_enabled: self.vars_enabled,
+                editor_submit: self.editor_submit,
+                multi_line_enabled: self.multi_line_enabled,
+                incomplete_predicate: self.incomplete_predicate,
+                highlighter: self.highlighter,
+                right_prompt: self.right_prompt.unwrap_or_default(),
+                transient_prompt: self.transient_prompt,
+                palette: self
+                    .theme
+                    .unwrap_or_default()
+                    .palette(ColorSupport::detect()),
+                ..LineData::new(self.prompt, self.prompt_end, !completion.is_empty())
+            },
             self.term,
             term_size,
             custom_quit,
             completion,
             self.starting_history,
             self.parse_err_hook,
-        ))
+        );
+        repl.dynamic_prompt = self.dynamic_prompt;
+        repl.status_bar = self.status_bar;
+        repl.log_level = SharedLevel::new(self.log_level.unwrap_or_default());
+        repl.transcript = transcript;
+        Ok(repl)
     }
 }
@@ -1,8 +1,14 @@
-use crate::line::Repl;
+use crate::line::{
+    style::QUOTES,
+    vars::{env_var_names, trailing_var_ref},
+    Repl,
+};
 
 use std::{
+    borrow::Cow,
     cmp::Ordering,
     collections::{HashMap, HashSet},
+    fmt::Display,
     io::{self, Write},
     ops::Range,
 };
@@ -17,6 +23,15 @@ const HELP_ARG: &str = "--help";
 const HELP_ARG_SHORT: &str = "-h";
 
 const USER_INPUT: i8 = -1;
+
+/// Chars that `shellwords_split` or a user's shell would interpret, recommendations containing any of these
+/// are quoted when they are inserted into the input line
+const SHELL_METACHARS: [char; 16] = [
+    '\'', '"', '\\', '$', '`', ';', '&', '|', '<', '>', '(', ')', '*', '?', '#', '~',
+];
+
+/// Chars that must be escaped with a backslash within a double quoted token
+pub(super) const DOUBLE_QUOTE_ESCAPES: [char; 4] = ['"', '\\', '$', '`'];
 const HELP: RecData = RecData::help();
 
 /// Static `RecData` node to signify that `HashIndex::Valid` and `HashIndex::Invalid` both do not have
@@ -243,7 +258,7 @@ impl RecData {
         let len = self.rec_len();
         self.alias
             .as_ref()
-            .map_or(len, |alias_mapping| len - alias_mapping.len())
+            .map_or(len, |alias_mapping| len.saturating_sub(alias_mapping.len()))
     }
 }
 
@@ -323,6 +338,107 @@ pub enum Direction {
     Previous,
 }
 
+/// The kind of error found when validating the input line against the set [`CommandScheme`]
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// The command is not a part of the [`CommandScheme`]
+    UnknownCommand,
+    /// The argument is not valid for the current command
+    UnknownArgument,
+    /// The value is not one of the pre-determined values of its `RecKind::Value`
+    InvalidValue,
+    /// The input does not pass the rule set with [`InnerScheme::with_parsing_rule`]
+    FailedParsingRule,
+    /// The current command is missing required user defined inputs
+    MissingRequired,
+    /// The current argument expects a value
+    MissingValue,
+    /// More inputs were supplied than what was expected
+    UnexpectedInput,
+}
+
+/// Structured description of an error found within the current input line
+///
+/// Diagnostics are updated along with completion suggestions, see: [`Repl::diagnostics`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    kind: DiagnosticKind,
+    span: Range<usize>,
+    message: String,
+}
+
+impl Diagnostic {
+    /// `span` must be a valid range into the given `line`
+    pub(super) fn new(kind: DiagnosticKind, span: Range<usize>, line: &str) -> Self {
+        let token = &line[span.clone()];
+        let message = match kind {
+            DiagnosticKind::UnknownCommand => format!("Unknown command: '{token}'"),
+            DiagnosticKind::UnknownArgument => format!("Unknown argument: '{token}'"),
+            DiagnosticKind::InvalidValue => format!("Invalid value: '{token}'"),
+            DiagnosticKind::FailedParsingRule => format!("Invalid input: '{token}'"),
+            DiagnosticKind::MissingRequired => String::from("Missing required input"),
+            DiagnosticKind::MissingValue => String::from("Missing value"),
+            DiagnosticKind::UnexpectedInput => format!("Unexpected input: '{token}'"),
+        };
+        Self {
+            kind,
+            span,
+            message,
+        }
+    }
+
+    #[inline]
+    fn offset_by(mut self, offset: usize) -> Self {
+        self.span = self.span.start + offset..self.span.end + offset;
+        self
+    }
+
+    /// Returns the kind of error found
+    #[inline]
+    pub fn kind(&self) -> DiagnosticKind {
+        self.kind
+    }
+
+    /// Returns the byte range of the offending text within [`Repl::input`]. The range is empty when the
+    /// error is caused by missing input
+    #[inline]
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Returns a human readable reason for the error
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Usage of the current command built from its [`CommandScheme`] node, eg. `roll [--sides <SIDES>]`
+pub(super) struct Signature {
+    pub(super) text: String,
+    /// Byte range within `text` of the input the user is currently expected to supply
+    pub(super) current: Option<Range<usize>>,
+}
+
+/// Role of a token within the input line according to the [`CommandScheme`], see: [`Repl::classify`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TokenKind {
+    Command,
+    Argument,
+    Value,
+    UserInput,
+    Invalid,
+    /// A trailing token that is still a prefix of a valid token
+    Partial,
+}
+
 impl Direction {
     #[inline]
     fn to_int(&self) -> i8 {
@@ -333,170 +449,358 @@ impl Direction {
     }
 }
 
-impl From<&'static CommandScheme> for Completion {
-    fn from(value: &'static CommandScheme) -> Self {
-        fn insert_index(
-            map: &mut HashMap<&'static str, HashIndex>,
-            key: &'static str,
-            val: usize,
-            data: &'static RecData,
-            list: &[&'static RecData],
-        ) {
-            if let Some(j) = map.insert(key, HashIndex::Entry(val)) {
-                assert_eq!(
-                    Completion::index_recs(list, j),
-                    data,
-                    "duplicate recommendation entries _must_ have identical nodes. Shared key in question: '{key}'"
-                )
+/// The kind of error found when validating a [`CommandScheme`]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemeErrorKind {
+    /// The node requires recommendations but none were supplied
+    MissingRecommendations,
+    /// The number of inner element descriptions does not match the number of unique recommendations
+    InnerLenMismatch { expected: usize, found: usize },
+    /// An argument node was supplied without inner element descriptions
+    MissingInner,
+    /// Inner element descriptions were supplied for a node that is not an argument
+    UnexpectedInner,
+    /// Shorts were supplied for a node that is not an argument
+    UnexpectedShort,
+    /// The short `'h'` was used, it is reserved for `-h` help arguments
+    ReservedShort,
+    /// The short is not exactly one char
+    InvalidShort(&'static str),
+    /// The alias index does not point to a recommendation
+    InvalidAlias(usize),
+    /// The key is shared by multiple recommendation entries that do not have identical nodes
+    DuplicateEntry(&'static str),
+    /// A `RecKind::Value` was set with a range that does not require any inputs
+    ValueWithoutRequired,
+    /// A `RecKind::Value` was set without any pre-determined values
+    ValueWithoutRecommendations,
+}
+
+impl Display for SchemeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingRecommendations => write!(f, "no recommendations were supplied"),
+            Self::InnerLenMismatch { expected, found } => write!(
+                f,
+                "invalid number of inner element descriptions, expected: {expected}, found: {found}"
+            ),
+            Self::MissingInner => write!(f, "inner elements not described"),
+            Self::UnexpectedInner => write!(
+                f,
+                "currently it is only valid to provide inner descriptions for arguments"
+            ),
+            Self::UnexpectedShort => write!(f, "shorts are only supported for arguments"),
+            Self::ReservedShort => write!(
+                f,
+                "the use of 'h' is not allowed, short arg '-h' is reserved for 'help'"
+            ),
+            Self::InvalidShort(short) => write!(f, "Short: {short}, is not a valid short format"),
+            Self::InvalidAlias(i) => write!(f, "alias index: {i}, does not point to a recommendation"),
+            Self::DuplicateEntry(key) => write!(
+                f,
+                "duplicate recommendation entries _must_ have identical nodes. Shared key in question: '{key}'"
+            ),
+            Self::ValueWithoutRequired => {
+                write!(f, "Values must have at least one required static input")
             }
+            Self::ValueWithoutRecommendations => write!(
+                f,
+                "`RecKind::Value` specified but no pre-determined values were supplied"
+            ),
         }
-        fn try_insert_rec_set(
-            kind: &RecKind,
-            map: &mut HashMap<usize, HashSet<&'static str>>,
-            recs: Option<&'static [&'static str]>,
-            at: usize,
-        ) {
-            if let RecKind::Value(r) = kind {
-                assert!(
-                    r.start > 0,
-                    "Values must have at least one required static input"
-                );
+    }
+}
+
+/// Error found when validating a [`CommandScheme`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemeError {
+    path: Vec<&'static str>,
+    kind: SchemeErrorKind,
+}
+
+impl SchemeError {
+    /// Returns the names of the recommendations that lead to the offending node. An empty path points to the
+    /// root of the [`CommandScheme`]
+    #[inline]
+    pub fn path(&self) -> &[&'static str] {
+        &self.path
+    }
+
+    /// Returns the kind of error found
+    #[inline]
+    pub fn kind(&self) -> &SchemeErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for SchemeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "root: {}", self.kind)
+        } else {
+            write!(f, "{}: {}", self.path.join(" > "), self.kind)
+        }
+    }
+}
+
+impl std::error::Error for SchemeError {}
+
+/// Collection of every [`SchemeError`] found within a [`CommandScheme`]
+///
+/// [`ReplBuilder::build`] returns this as the inner error of an [`io::Error`] with the kind
+/// [`io::ErrorKind::InvalidInput`]
+///
+/// [`ReplBuilder::build`]: crate::line::ReplBuilder::build
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemeErrors(Vec<SchemeError>);
+
+impl SchemeErrors {
+    /// Returns all errors found
+    #[inline]
+    pub fn errors(&self) -> &[SchemeError] {
+        &self.0
+    }
+}
+
+impl IntoIterator for SchemeErrors {
+    type Item = SchemeError;
+    type IntoIter = std::vec::IntoIter<SchemeError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl Display for SchemeErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid `CommandScheme`, found {} error(s)",
+            self.0.len()
+        )?;
+        self.0.iter().try_for_each(|err| write!(f, "\n  {err}"))
+    }
+}
+
+impl std::error::Error for SchemeErrors {}
+
+impl CommandScheme {
+    /// Checks that the scheme is well formed, returning every error that was found
+    pub fn validate(&'static self) -> Result<(), SchemeErrors> {
+        Completion::try_from_scheme(self).map(|_| ())
+    }
+
+    /// Panics displaying every error found if the scheme is not well formed. Intended to be used within
+    /// unit tests so ill formed schemes are caught before [`ReplBuilder::build`] is called
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// #[test]
+    /// fn command_scheme_is_valid() {
+    ///     COMPLETION.assert_valid();
+    /// }
+    /// ```
+    ///
+    /// [`ReplBuilder::build`]: crate::line::ReplBuilder::build
+    #[track_caller]
+    pub fn assert_valid(&'static self) {
+        if let Err(errors) = self.validate() {
+            panic!("{errors}")
+        }
+    }
+}
+
+/// Walks a [`CommandScheme`] flattening it into the lookup tables used by [`Completion`], recording every error
+/// found along the way
+#[derive(Default)]
+struct SchemeWalker {
+    rec_list: Vec<&'static RecData>,
+    rec_map: HashMap<&'static str, HashIndex>,
+    value_sets: HashMap<usize, HashSet<&'static str>>,
+    /// Names of the recommendations that lead to the node currently being walked
+    path: Vec<&'static str>,
+    errors: Vec<SchemeError>,
+}
 
-                const NOT_SUPPLIED: &str =
-                    "`RecKind::Value` specified but no pre-determined values were supplied";
-                let value_set = HashSet::from_iter(recs.expect(NOT_SUPPLIED).iter().copied());
+impl SchemeWalker {
+    #[inline]
+    fn err(&mut self, kind: SchemeErrorKind) {
+        self.errors.push(SchemeError {
+            path: self.path.clone(),
+            kind,
+        });
+    }
 
-                assert!(!value_set.is_empty(), "{NOT_SUPPLIED}");
-                assert!(map.insert(at, value_set).is_none())
+    fn insert_index(&mut self, key: &'static str, val: usize, data: &'static RecData) {
+        if let Some(j) = self.rec_map.insert(key, HashIndex::Entry(val)) {
+            if Completion::index_recs(&self.rec_list, j) != data {
+                self.err(SchemeErrorKind::DuplicateEntry(key));
             }
         }
-        fn try_insert_aliases(
-            map: &mut HashMap<&'static str, HashIndex>,
-            val: usize,
-            data: &'static RecData,
-            list: &[&'static RecData],
-            mapping: Option<&'static [(usize, usize)]>,
-            recs: Option<&'static [&'static str]>,
-            target: usize,
-        ) {
-            if let Some(rec_mapping) = mapping {
-                rec_mapping
-                    .iter()
-                    .filter(|(rec_i, _)| *rec_i == target)
-                    .map(|&(_, alias_i)| {
-                        recs.expect("tried to set alias when no recommendations were supplied")
-                            [alias_i]
-                    })
-                    .for_each(|alias| {
-                        insert_index(map, alias, val, data, list);
-                    });
+    }
+
+    fn try_insert_rec_set(&mut self, data: &'static RecData, at: usize) {
+        let RecKind::Value(range) = &data.kind else {
+            return;
+        };
+        if range.start == 0 {
+            self.err(SchemeErrorKind::ValueWithoutRequired);
+        }
+        match data.recs.filter(|recs| !recs.is_empty()) {
+            Some(recs) => {
+                self.value_sets
+                    .insert(at, HashSet::from_iter(recs.iter().copied()));
             }
+            None => self.err(SchemeErrorKind::ValueWithoutRecommendations),
         }
+    }
 
-        fn walk_inner(
-            inner: &'static InnerScheme,
-            list: &mut Vec<&'static RecData>,
-            map: &mut HashMap<&'static str, HashIndex>,
-            value_sets: &mut HashMap<usize, HashSet<&'static str>>,
-        ) {
-            match inner.data {
-                RecData {
-                    alias,
-                    recs,
-                    short,
-                    kind: RecKind::Argument(_),
-                    ..
-                } => {
-                    let expected_len = inner.data.unique_rec_end();
-                    let inner_inner = inner.inner.expect("inner elements not described");
-                    assert_eq!(
-                        expected_len,
-                        inner_inner.len(),
-                        "invalid number of inner element descriptions"
-                    );
-                    for (i, (&argument, inner)) in recs
-                        .expect("is some")
-                        .iter()
-                        .zip(inner_inner)
-                        .enumerate()
-                        .take(expected_len)
-                    {
-                        list.push(&inner.data);
-                        let l_i = list.len() - 1;
-                        insert_index(map, argument, l_i, &inner.data, list);
-                        if let Some(&(_, short_ch)) = short.and_then(|short_mapping| {
-                            short_mapping.iter().find(|(map_i, _)| *map_i == i)
-                        }) {
-                            assert_ne!(short_ch, HELP_SHORT, "the use of 'h' is not allowed, short arg '-h' is reserved for 'help'");
-                            assert!(
-                                short_ch.chars().count() == 1,
-                                "Short: {short_ch}, is not a valid short format"
-                            );
-                            insert_index(map, short_ch, l_i, &inner.data, list);
-                        }
-                        try_insert_aliases(map, l_i, &inner.data, list, alias, recs, i);
-                        try_insert_rec_set(&inner.data.kind, value_sets, inner.data.recs, l_i);
-                        walk_inner(inner, list, map, value_sets);
-                    }
-                }
-                _ => {
-                    assert!(
-                        inner.inner.is_none(),
-                        "currently it is only valid to provide inner descriptions for arguments"
-                    );
-                    assert!(
-                        inner.data.short.is_none(),
-                        "shorts are only supported for arguments"
-                    );
+    fn try_insert_aliases(
+        &mut self,
+        val: usize,
+        data: &'static RecData,
+        mapping: Option<&'static [(usize, usize)]>,
+        recs: &'static [&'static str],
+        target: usize,
+    ) {
+        let Some(rec_mapping) = mapping else {
+            return;
+        };
+        for &(_, alias_i) in rec_mapping.iter().filter(|(rec_i, _)| *rec_i == target) {
+            match recs.get(alias_i) {
+                Some(alias) => self.insert_index(alias, val, data),
+                None => self.err(SchemeErrorKind::InvalidAlias(alias_i)),
+            }
+        }
+    }
+
+    /// Expects the name of the given `inner` node to be the last entry of `self.path`
+    fn walk_inner(&mut self, inner: &'static InnerScheme) {
+        let RecData {
+            alias,
+            recs,
+            short,
+            kind: RecKind::Argument(_),
+            ..
+        } = inner.data
+        else {
+            if inner.inner.is_some() {
+                self.err(SchemeErrorKind::UnexpectedInner);
+            }
+            if inner.data.short.is_some() {
+                self.err(SchemeErrorKind::UnexpectedShort);
+            }
+            return;
+        };
+
+        let Some(recs) = recs else {
+            return self.err(SchemeErrorKind::MissingRecommendations);
+        };
+        let Some(inner_inner) = inner.inner else {
+            return self.err(SchemeErrorKind::MissingInner);
+        };
+        let expected_len = inner.data.unique_rec_end();
+        if expected_len != inner_inner.len() {
+            self.err(SchemeErrorKind::InnerLenMismatch {
+                expected: expected_len,
+                found: inner_inner.len(),
+            });
+        }
+
+        for (i, (&argument, inner)) in recs.iter().zip(inner_inner).enumerate().take(expected_len) {
+            self.path.push(argument);
+            self.rec_list.push(&inner.data);
+            let l_i = self.rec_list.len() - 1;
+            self.insert_index(argument, l_i, &inner.data);
+            if let Some(&(_, short_ch)) =
+                short.and_then(|short_mapping| short_mapping.iter().find(|(map_i, _)| *map_i == i))
+            {
+                if short_ch == HELP_SHORT {
+                    self.err(SchemeErrorKind::ReservedShort);
+                } else if short_ch.chars().count() != 1 {
+                    self.err(SchemeErrorKind::InvalidShort(short_ch));
+                } else {
+                    self.insert_index(short_ch, l_i, &inner.data);
                 }
             }
+            self.try_insert_aliases(l_i, &inner.data, alias, recs, i);
+            self.try_insert_rec_set(&inner.data, l_i);
+            self.walk_inner(inner);
+            self.path.pop();
         }
+    }
+}
+
+impl From<&'static CommandScheme> for Completion {
+    /// # Panics
+    ///
+    /// If the given scheme is not well formed, prefer [`Completion::try_from_scheme`] which returns every error
+    /// that was found
+    #[track_caller]
+    fn from(value: &'static CommandScheme) -> Self {
+        Completion::try_from_scheme(value).unwrap_or_else(|errors| panic!("{errors}"))
+    }
+}
+
+impl Completion {
+    /// Flattens the given scheme into a `Completion`, returning every error found if the scheme is not well formed
+    pub fn try_from_scheme(value: &'static CommandScheme) -> Result<Self, SchemeErrors> {
+        let mut walker = SchemeWalker {
+            rec_list: vec![&value.commands],
+            rec_map: HashMap::from([(HELP_STR, HashIndex::Help)]),
+            ..Default::default()
+        };
 
+        let Some(commands) = value.commands.recs else {
+            walker.err(SchemeErrorKind::MissingRecommendations);
+            return Err(SchemeErrors(walker.errors));
+        };
+        if value.commands.short.is_some() {
+            walker.err(SchemeErrorKind::UnexpectedShort);
+        }
         let expected_len = value.commands.unique_rec_end();
-        assert_eq!(expected_len, value.inner.len());
-        assert!(value.commands.short.is_none());
-        let mut rec_map = HashMap::new();
-        let mut value_sets = HashMap::new();
-        let mut rec_list = vec![&value.commands];
-        rec_map.insert(HELP_STR, HashIndex::Help);
-        for (i, (&command, inner)) in value
-            .commands
-            .recs
-            .expect("is some")
+        if expected_len != value.inner.len() {
+            walker.err(SchemeErrorKind::InnerLenMismatch {
+                expected: expected_len,
+                found: value.inner.len(),
+            });
+        }
+
+        for (i, (&command, inner)) in commands
             .iter()
             .zip(value.inner.iter())
             .enumerate()
             .take(expected_len)
         {
-            rec_list.push(&inner.data);
-            let l_i = rec_list.len() - 1;
-            insert_index(&mut rec_map, command, l_i, &inner.data, &rec_list);
-            try_insert_aliases(
-                &mut rec_map,
-                l_i,
-                &inner.data,
-                &rec_list,
-                value.commands.alias,
-                value.commands.recs,
-                i,
-            );
-            try_insert_rec_set(&inner.data.kind, &mut value_sets, inner.data.recs, l_i);
-            walk_inner(inner, &mut rec_list, &mut rec_map, &mut value_sets);
+            walker.path.push(command);
+            walker.rec_list.push(&inner.data);
+            let l_i = walker.rec_list.len() - 1;
+            walker.insert_index(command, l_i, &inner.data);
+            walker.try_insert_aliases(l_i, &inner.data, value.commands.alias, commands, i);
+            walker.try_insert_rec_set(&inner.data, l_i);
+            walker.walk_inner(inner);
+            walker.path.pop();
         }
-        let mut recommendations = value
-            .commands
-            .recs
-            .expect("`CommandScheme` supplied with no recommendations")[..expected_len]
-            .to_vec();
+
+        if !walker.errors.is_empty() {
+            return Err(SchemeErrors(walker.errors));
+        }
+
+        let mut recommendations = commands[..expected_len].to_vec();
         recommendations.push(HELP_STR);
-        Self {
+        Ok(Self {
             recommendations,
             input: CompletionState::default(),
-            rec_map,
-            rec_list: rec_list.into_boxed_slice(),
-            value_sets,
+            rec_map: walker.rec_map,
+            rec_list: walker.rec_list.into_boxed_slice(),
+            value_sets: walker.value_sets,
             indexer: Indexer::default(),
-        }
+            var: None,
+            env_vars: None,
+            classified: Classified::default(),
+        })
     }
 }
 
@@ -514,9 +818,51 @@ pub struct Completion {
     rec_list: Box<[&'static RecData]>,
     rec_map: HashMap<&'static str, HashIndex>,
     value_sets: HashMap<usize, HashSet<&'static str>>,
+    var: Option<VarCompletion>,
+    /// Names of the environment variables, taken once the `$` at the contained byte index is typed so the
+    /// environment is not read on every key press
+    env_vars: Option<(usize, Vec<String>)>,
+    /// Token kinds of the last classified line, see: [`Completion::classify`]
+    classified: Classified,
+}
+
+/// Token kinds of a classified line along with the walk state after each completed token, so an edit of the line
+/// only walks the tokens that follow the last unchanged token
+#[derive(Default)]
+struct Classified {
+    /// The classified line with its arg assignments split and its leading whitespace trimmed
+    line: String,
+    tokens: Vec<ClassifiedToken>,
+    /// The token still being typed at the end of the line, if one exists and could be classified
+    trailing: Option<(Range<usize>, TokenKind)>,
+}
+
+/// A token that is followed by whitespace within a [`Classified`] line
+struct ClassifiedToken {
+    /// Byte range of the token within the line
+    span: Range<usize>,
+    /// Byte index of the end of the walked prefix, that is the start of the next token or the end of the line
+    end: usize,
+    kind: Option<TokenKind>,
+    input: CompletionState,
+    indexer: Indexer,
+}
+
+/// Suggestions for a `$` variable reference that is being typed at the end of the input line
+struct VarCompletion {
+    /// Byte index of the `$` within `LineData.input`
+    start: usize,
+    braced: bool,
+    /// The partial name the user typed
+    input: String,
+    /// Names of known variables that start with `input`
+    recs: Vec<String>,
+    /// Index of the currently suggested name, `None` when the user's input is displayed
+    i: Option<usize>,
 }
 
 /// `Indexer` keeps track of various indexes for the current suggestion state
+#[derive(Clone)]
 struct Indexer {
     /// `list.0` points to the currently used [`RecData`] in [`Completion.rec_list`]  
     /// `list.1` is only used when `Self.multiple`
@@ -554,12 +900,14 @@ impl Default for Indexer {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct CompletionState {
     curr_command: Option<SliceData>,
     curr_argument: Option<SliceData>,
     curr_value: Option<SliceData>,
     required_input_i: Vec<usize>,
+    /// The last flag or value the walk resolved and moved on from within the same walk
+    last_resolved: Option<SliceData>,
     ending: LineEnd,
 }
 
@@ -639,7 +987,8 @@ impl SliceData {
             }
             RecKind::UserDefined { range, parse_fn }
                 if range.contains(&arg_count.unwrap_or(1))
-                    && parse_fn.map_or(true, |valid| valid(data.to_slice_unchecked(line))) =>
+                    && parse_fn
+                        .map_or(true, |valid| valid(unquote(data.to_slice_unchecked(line)))) =>
             {
                 data.hash_i = HashIndex::Valid
             }
@@ -649,7 +998,7 @@ impl SliceData {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 struct LineEnd {
     token: String,
     open_quote: Option<(usize, char)>,
@@ -833,12 +1182,21 @@ impl Completion {
     }
     #[inline]
     fn trailing<'a>(&self, line_trim_start: &'a str) -> &'a str {
-        self.last_key().map_or(line_trim_start, |key| {
-            let trim = line_trim_start[key.byte_end_i()..].trim();
-            trim.strip_prefix(HELP_ARG)
-                .or(trim.strip_prefix(HELP_ARG_SHORT))
-                .unwrap_or(trim)
-        })
+        &line_trim_start[self.trailing_range(line_trim_start)]
+    }
+    /// Byte range of the input that follows the last key, excluding surrounding whitespace and a leading help arg
+    fn trailing_range(&self, line_trim_start: &str) -> Range<usize> {
+        let Some(key) = self.last_key() else {
+            return 0..line_trim_start.len();
+        };
+        let after_key = &line_trim_start[key.byte_end_i()..];
+        let start = line_trim_start.len() - after_key.trim_start().len();
+        let end = (key.byte_end_i() + after_key.trim_end().len()).max(start);
+        let help_len = [HELP_ARG, HELP_ARG_SHORT]
+            .into_iter()
+            .find(|help| line_trim_start[start..end].starts_with(help))
+            .map_or(0, str::len);
+        start + help_len..end
     }
     #[inline]
     fn arg_or_cmd(&self) -> Option<&SliceData> {
@@ -953,7 +1311,7 @@ impl Completion {
     /// Caller must ensure that the given line is `LineData.input.trim_start()` as internally
     /// [SliceData::to_slice_unchecked] is called
     fn hash_command_unchecked(&self, line: &str, command: &mut SliceData) {
-        let command_str = command.to_slice_unchecked(line);
+        let command_str = unquote(command.to_slice_unchecked(line));
         if command_str.starts_with('-') {
             return;
         }
@@ -985,21 +1343,61 @@ impl Completion {
     /// [SliceData::to_slice_unchecked] is called
     fn hash_arg_unchecked(&self, line_trim_start: &str, arg: &mut SliceData) {
         let arg_str = arg.to_slice_unchecked(line_trim_start);
-        if !arg_str.starts_with('-') {
+        let Some(short_str) = arg_str.strip_prefix('-') else {
             return;
-        }
-        if let Some(i) =
-            self.trimmed_arg_valid_i_unchecked(arg_str.trim_start_matches('-'), line_trim_start)
+        };
+        if let Some(i) = self
+            .trimmed_arg_valid_i_unchecked(arg_str.trim_start_matches('-'), line_trim_start)
+            .or_else(|| self.short_cluster_valid_i_unchecked(short_str, line_trim_start))
         {
             arg.hash_i = i;
         }
     }
 
+    /// Validates POSIX style clusters of short args, eg. `-rc` for `-r -c`. Every short in the given `cluster`
+    /// must be valid for the current command and all but the last short must be a `RecKind::ArgFlag`.
+    /// Returns the `HashIndex` of the last short in the cluster, as it is the only one that can take values
+    fn short_cluster_valid_i_unchecked(
+        &self,
+        cluster: &str,
+        line_trim_start: &str,
+    ) -> Option<HashIndex> {
+        let cmd = self
+            .curr_command()
+            .expect("can only set arg if command is valid")
+            .to_slice_unchecked(line_trim_start);
+        self.short_cluster_valid_i(cluster, cmd)
+    }
+
+    /// See: [`Self::short_cluster_valid_i_unchecked`]
+    fn short_cluster_valid_i(&self, cluster: &str, cmd: &str) -> Option<HashIndex> {
+        if cluster.starts_with('-') || cluster.chars().nth(1).is_none() {
+            return None;
+        }
+        let mut shorts = cluster.char_indices().peekable();
+        while let Some((i, ch)) = shorts.next() {
+            let short = &cluster[i..i + ch.len_utf8()];
+            let hash_i = self.arg_valid_i(short, cmd)?;
+            if shorts.peek().is_none() {
+                return Some(hash_i);
+            }
+            if Completion::index_recs(&self.rec_list, hash_i).kind != RecKind::ArgFlag {
+                return None;
+            }
+        }
+        None
+    }
+
     fn trimmed_arg_valid_i_unchecked(&self, arg: &str, line_trim_start: &str) -> Option<HashIndex> {
         let cmd = self
             .curr_command()
             .expect("can only set arg if command is valid")
             .to_slice_unchecked(line_trim_start);
+        self.arg_valid_i(arg, cmd)
+    }
+
+    /// Returns the `HashIndex` of the given trimmed `arg` if it is valid for the given `cmd`
+    fn arg_valid_i(&self, arg: &str, cmd: &str) -> Option<HashIndex> {
         let i = self.rec_map.get(arg).copied()?;
         if match Completion::index_recs(&self.rec_list, i).parent {
             // `hash_command_unchecked` _only_ provides case leeway for 'Pascal Case' commands
@@ -1026,7 +1424,7 @@ impl Completion {
             return;
         }
 
-        let val_str = value.to_slice_unchecked(line);
+        let val_str = unquote(value.to_slice_unchecked(line));
         if val_str.starts_with('-') {
             return;
         }
@@ -1058,239 +1456,65 @@ impl Completion {
         }
     }
 
-    /// Will panic if `self.completion.is_empty()`
-    fn set_default_recommendations_unchecked(&mut self) {
-        let commands = Self::get_commands(&self.rec_list);
-        self.recommendations = commands.recs.as_ref().expect("commands is not empty")
-            [..commands.unique_rec_end()]
-            .to_vec();
-        self.recommendations.push(HELP_STR);
+    /// Returns the kind of a valid value given to the current argument or command
+    fn value_token_kind(&self) -> Option<TokenKind> {
+        match Self::index_recs(&self.rec_list, self.arg_or_cmd()?.hash_i).kind {
+            RecKind::Value(_) => Some(TokenKind::Value),
+            RecKind::UserDefined { .. } => Some(TokenKind::UserInput),
+            _ => None,
+        }
     }
-}
 
-fn strip_dashes(str: &str) -> (usize, Option<&str>) {
-    let dashes = str.chars().take_while(|&c| c == '-').count();
-    (dashes, str.get(dashes..).filter(|&r| !r.is_empty()))
-}
+    /// Walks the given `line_trim_start` with the [`CommandScheme`] updating the state of the last command, argument,
+    /// and value that were entered. The state is updated incrementally so it must be walked on every edit of the
+    /// line. Returns `false` if no further progress can be made as the line has no valid entry to walk from
+    fn walk(&mut self, line_trim_start: &str) -> bool {
+        self.input.last_resolved = None;
+        self.input.update_curr_token(line_trim_start);
+        let state_changed = self.input.check_state(line_trim_start);
 
-impl<Ctx, W: Write> Repl<Ctx, W> {
-    #[inline]
-    fn curr_token(&self) -> &str {
-        &self.completion.input.ending.token
-    }
+        if !state_changed && self.indexer.list.0 == HashIndex::Invalid {
+            return false;
+        }
 
-    #[inline]
-    fn open_quote(&self) -> Option<&(usize, char)> {
-        self.completion.input.ending.open_quote.as_ref()
-    }
+        let multiple_switch_kind = self.indexer.multiple
+            && line_trim_start.ends_with(char::is_whitespace)
+            && line_trim_start
+                .split_whitespace()
+                .next_back()
+                .is_some_and(|end_token| end_token.starts_with('-'));
 
-    fn kind_err_conditions(&self, idx: HashIndex, curr_token: &str, trailing: &str) -> bool {
-        let rec = Completion::index_recs(&self.completion.rec_list, idx);
-        let has_help = if idx == HashIndex::Valid {
-            self.completion
-                .curr_command()
-                .map(|cmd| Completion::index_recs(&self.completion.rec_list, cmd.hash_i).has_help)
-                .expect("valid can only be set once a base entry is provided")
-        } else {
-            rec.has_help
-        };
-        match rec.kind {
-            RecKind::Argument(required) => {
-                match required.cmp(
-                    &(self.completion.input.required_input_i.len()
-                        + !curr_token.is_empty() as usize),
-                ) {
-                    Ordering::Greater => true,
-                    Ordering::Equal => false,
-                    Ordering::Less => {
-                        match strip_dashes(curr_token) {
-                            (0, Some(_)) => true,
-                            (0..=2, None) => false,
-                            (1, Some(HELP_SHORT)) => {
-                                let parent_hash = self
-                                    .completion
-                                    .curr_command()
-                                    .expect("can only set arg if command is valid")
-                                    .hash_i;
-                                !Completion::index_recs(&self.completion.rec_list, parent_hash)
-                                    .has_help
-                            }
-                            (1, Some(input)) if input.chars().nth(1).is_none() => self
-                                .completion
-                                // input line is calculated the same way it is within `update_completion`
-                                .trimmed_arg_valid_i_unchecked(input, self.line.input.trim_start())
-                                .is_none(),
-                            (2, Some(input)) => !self.completion.valid_rec_prefix(input, has_help),
-                            _ => true,
-                        }
-                    }
-                }
-            }
-            _ if curr_token.starts_with('-') => match strip_dashes(curr_token) {
-                (0..=2, None) | (1, Some(HELP_SHORT)) | (2, Some(HELP_STR)) => !has_help,
-                (2, Some(input)) => !self.completion.valid_rec_prefix(input, has_help),
-                _ => true,
-            },
-            RecKind::Command => !self.completion.valid_rec_prefix(curr_token, has_help),
-            // other `Value` and `UserDefined` errors do not need to be checked since `update_completion`
-            // will set `curr_value` to an invalid instance returning the error condition prior this fn call
-            RecKind::UserDefined { parse_fn, .. } => {
-                trailing.is_empty() || parse_fn.is_some_and(|valid| !valid(curr_token))
-            }
-            RecKind::Value(_) => {
-                trailing.is_empty() || !self.completion.valid_rec_prefix(curr_token, has_help)
-            }
-            RecKind::ArgFlag | RecKind::Help | RecKind::Null => !trailing.is_empty(),
+        if multiple_switch_kind {
+            self.indexer.multiple = false;
         }
-    }
 
-    fn check_value_err(&self, line_trim_start: &str) -> bool {
-        let rec_list = [
-            self.completion.indexer.list.0,
-            self.completion.indexer.list.1,
-        ];
-        let curr_token = self.curr_token();
-        let trailing = self.completion.trailing(line_trim_start);
-        let mut errs = [false, false];
-        for (err, hash) in errs.iter_mut().zip(rec_list) {
-            *err = self.kind_err_conditions(hash, curr_token, trailing);
-            if !self.completion.indexer.multiple {
-                return *err;
-            }
-        }
-        if curr_token.starts_with('-')
-            && matches!(
-                Completion::index_recs(&self.completion.rec_list, rec_list[1]).kind,
-                RecKind::Argument(_)
-            )
-        {
-            return errs[1];
+        self.indexer.recs = USER_INPUT;
+
+        if self.curr_command().is_none() && self.open_quote().is_none() {
+            self.input.curr_command =
+                line_trim_start
+                    .split_once(char::is_whitespace)
+                    .map(|(pre, _)| {
+                        SliceData::from_raw_unchecked(
+                            0,
+                            pre.len(),
+                            &RecKind::Command,
+                            line_trim_start,
+                            self,
+                            None,
+                        )
+                    });
         }
-        errs[0] && errs[1]
-    }
-
-    #[inline]
-    fn add_help(&self, recs: [(&RecData, HashIndex); 2], line_trim_start: &str) -> bool {
-        let last_rec = recs[0].0;
 
-        last_rec.has_help
-            && (!matches!(
-                last_rec.kind,
-                RecKind::Value(_) | RecKind::UserDefined { .. }
-            ) || {
-                let trailing = self.completion.trailing(line_trim_start);
-                !self.kind_err_conditions(recs[0].1, self.curr_token(), trailing)
-            })
-            || recs[1].0.has_help
-                && (self.completion.indexer.multiple
-                    || self
-                        .completion
-                        .input
-                        .curr_value
-                        .is_some_and(|v| v.hash_i == HashIndex::Valid))
-    }
-
-    fn check_for_errors(&self, line_trim_start: &str) -> bool {
-        self.completion.curr_command().is_some_and_invalid()
-            || self.completion.curr_arg().is_some_and_invalid()
-            || self.completion.curr_value().is_some_and_invalid()
-            || self.check_value_err(line_trim_start)
-    }
-
-    fn try_get_forward_arg_or_val(
-        &self,
-        line_trim_start: &str,
-        command_kind: &RecKind,
-    ) -> Option<SliceData> {
-        let (kind_match, nvals) = self
-            .completion
-            .count_vals_in_slice(line_trim_start, command_kind);
-
-        if let Some(starting_token) = &kind_match {
-            let start_token_meta =
-                Completion::index_recs(&self.completion.rec_list, starting_token.hash_i);
-
-            if starting_token.hash_i == HashIndex::Invalid || nvals == 0 {
-                return kind_match;
-            }
-
-            if let RecData {
-                kind: RecKind::Value(range) | RecKind::UserDefined { range, .. },
-                end: false,
-                ..
-            } = start_token_meta
-            {
-                if range.contains(&nvals) {
-                    return None;
-                }
-            }
-        }
-
-        self.completion
-            .try_parse_token_from_end(line_trim_start, command_kind, Some(nvals))
-    }
-
-    /// Updates the suggestions for the current user input
-    pub fn update_completion(&mut self) {
-        if !self.line.comp_enabled {
-            return;
-        }
-
-        let line_trim_start = self.line.input.trim_start();
-        if line_trim_start.is_empty() {
-            // `comp_enabled` can only be set when `!Completion.is_empty()` via checks in `enable_completion`
-            // and `ReplBuilder::build`. Making it safe to call `default_recommendations` here
-            self.completion.set_default_recommendations_unchecked();
-            self.line.err = false;
-            self.completion.input.ending = LineEnd::default();
-            return;
-        }
-
-        self.completion.input.update_curr_token(line_trim_start);
-        let state_changed = self.completion.input.check_state(line_trim_start);
-
-        if !state_changed && self.completion.indexer.list.0 == HashIndex::Invalid {
-            self.line.err = self.check_for_errors(line_trim_start);
-            return;
-        }
-
-        let multiple_switch_kind = self.completion.indexer.multiple
-            && line_trim_start.ends_with(char::is_whitespace)
-            && line_trim_start
-                .split_whitespace()
-                .next_back()
-                .is_some_and(|end_token| end_token.starts_with('-'));
-
-        if multiple_switch_kind {
-            self.completion.indexer.multiple = false;
-        }
-
-        self.completion.indexer.recs = USER_INPUT;
-
-        if self.completion.curr_command().is_none() && self.open_quote().is_none() {
-            self.completion.input.curr_command = line_trim_start
-                .split_once(char::is_whitespace)
-                .map(|(pre, _)| {
-                    SliceData::from_raw_unchecked(
-                        0,
-                        pre.len(),
-                        &RecKind::Command,
-                        line_trim_start,
-                        &self.completion,
-                        None,
-                    )
-                });
-        }
-
-        if let Some((cmd_kind, cmd_suf)) = self.completion.curr_command().and_then(|cmd| {
+        if let Some((cmd_kind, cmd_suf)) = self.curr_command().and_then(|cmd| {
             if self.open_quote().is_some()
-                || self.completion.curr_value().is_some()
-                || (self.completion.curr_arg().is_some() && !multiple_switch_kind)
+                || self.curr_value().is_some()
+                || (self.curr_arg().is_some() && !multiple_switch_kind)
             {
                 return None;
             }
 
-            let command_kind = &Completion::index_recs(&self.completion.rec_list, cmd.hash_i).kind;
+            let command_kind = &Completion::index_recs(&self.rec_list, cmd.hash_i).kind;
             let cmd_suf = line_trim_start[cmd.slice_len..].trim_start();
             (matches!(command_kind, RecKind::Argument(_) | RecKind::Value(_))
                 && !cmd_suf.is_empty())
@@ -1300,23 +1524,23 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
                 self.try_get_forward_arg_or_val(line_trim_start, cmd_kind)
             } else {
                 // make sure we set prev arg when backspacing
-                let (kind_match, nvals) = self.completion.count_vals_in_slice(
+                let (kind_match, nvals) = self.count_vals_in_slice(
                     &line_trim_start[..line_trim_start.len() - self.curr_token().len()],
                     cmd_kind,
                 );
 
                 kind_match.filter(|starting_token| {
-                    if starting_token.exact_eq(self.completion.curr_command().expect("outer if")) {
+                    if starting_token.exact_eq(self.curr_command().expect("outer if")) {
                         return false;
                     }
 
                     let starting_token =
-                        Completion::index_recs(&self.completion.rec_list, starting_token.hash_i);
+                        Completion::index_recs(&self.rec_list, starting_token.hash_i);
                     match &starting_token.kind {
                         RecKind::Value(range) | RecKind::UserDefined { range, .. }
                             if range.contains(&(nvals + 1)) =>
                         {
-                            self.completion.indexer.multiple = nvals >= range.start;
+                            self.indexer.multiple = nvals >= range.start;
                             true
                         }
                         _ => starting_token.end,
@@ -1345,44 +1569,41 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
                                 hash_i: HashIndex::Invalid,
                                 ..
                             },
-                        ) if self.completion.input.required_input_i.len() < required => self
-                            .completion
-                            .input
-                            .required_input_i
-                            .push(invalid.byte_end_i()),
-                        _ => self.completion.input.curr_argument = new,
+                        ) if self.input.required_input_i.len() < required => {
+                            self.input.required_input_i.push(invalid.byte_end_i())
+                        }
+                        _ => self.input.curr_argument = new,
                     }
                 }
-                RecKind::Value(_) => self.completion.input.curr_value = new,
+                RecKind::Value(_) => self.input.curr_value = new,
                 _ => unreachable!("by outer if"),
             }
         }
 
-        if let Some(end) = self.completion.curr_arg().and_then(|arg| {
-            let rec_data = Completion::index_recs(&self.completion.rec_list, arg.hash_i);
+        if let Some(end) = self.curr_arg().and_then(|arg| {
+            let rec_data = Completion::index_recs(&self.rec_list, arg.hash_i);
             (rec_data.kind == RecKind::ArgFlag).then_some(rec_data.end)
         }) {
             // boolean flag found, ok to move on
             if !end {
-                self.completion.input.curr_argument = None;
+                self.input.last_resolved = self.input.curr_argument.take();
             }
         } else if let Some((cmd_kind, arg_slice, range, arg_suf)) =
-            self.completion.curr_command().and_then(|cmd| {
+            self.curr_command().and_then(|cmd| {
                 if cmd.hash_i == HashIndex::Invalid
-                    || self.completion.curr_value().is_some()
+                    || self.curr_value().is_some()
                     || self.open_quote().is_some()
                 {
                     return None;
                 }
 
-                self.completion.curr_arg().and_then(|arg| {
+                self.curr_arg().and_then(|arg| {
                     let (RecKind::Value(range) | RecKind::UserDefined { range, .. }) =
-                        &Completion::index_recs(&self.completion.rec_list, arg.hash_i).kind
+                        &Completion::index_recs(&self.rec_list, arg.hash_i).kind
                     else {
                         return None;
                     };
-                    let cmd_kind =
-                        &Completion::index_recs(&self.completion.rec_list, cmd.hash_i).kind;
+                    let cmd_kind = &Completion::index_recs(&self.rec_list, cmd.hash_i).kind;
                     let arg_suf = line_trim_start[arg.byte_end_i()..].trim_start();
                     (arg.hash_i != HashIndex::Invalid && !arg_suf.is_empty())
                         .then_some((cmd_kind, arg, range, arg_suf))
@@ -1390,51 +1611,46 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
             })
         {
             if arg_suf.ends_with(char::is_whitespace) {
-                let arg_data = Completion::index_recs(&self.completion.rec_list, arg_slice.hash_i);
+                let arg_data = Completion::index_recs(&self.rec_list, arg_slice.hash_i);
 
                 if let Some(token) =
-                    self.completion
-                        .try_parse_token_from_end(line_trim_start, &arg_data.kind, None)
+                    self.try_parse_token_from_end(line_trim_start, &arg_data.kind, None)
                 {
                     if token.hash_i != HashIndex::Invalid && !arg_data.end {
-                        let (kind_match, nvals) = self
-                            .completion
-                            .count_vals_in_slice(line_trim_start, cmd_kind);
+                        let (kind_match, nvals) =
+                            self.count_vals_in_slice(line_trim_start, cmd_kind);
                         debug_assert!(kind_match.unwrap().exact_eq(arg_slice));
 
                         if range.contains(&(nvals + 1)) {
-                            self.completion.indexer.multiple = true;
+                            self.indexer.multiple = true;
                         } else {
-                            self.completion.indexer.multiple = false;
-                            self.completion.input.curr_argument = None;
+                            self.indexer.multiple = false;
+                            self.input.curr_argument = None;
                         }
+                        self.input.last_resolved = Some(token);
                     } else {
-                        self.completion.input.curr_value = Some(token);
+                        self.input.curr_value = Some(token);
                     }
                 }
             } else {
                 // make sure we set multiple to false when backspacing
-                let (kind_match, nvals) = self.completion.count_vals_in_slice(
+                let (kind_match, nvals) = self.count_vals_in_slice(
                     &line_trim_start[..line_trim_start.len() - self.curr_token().len()],
                     cmd_kind,
                 );
                 debug_assert!(kind_match.unwrap().exact_eq(arg_slice));
-                self.completion.indexer.multiple = range.contains(&nvals);
+                self.indexer.multiple = range.contains(&nvals);
             }
         }
 
         // writeln!(
         //     get_debugger(),
         //     "{}",
-        //     self.completion.input.debug(line_trim_start)
+        //     self.input.debug(line_trim_start)
         // )
         // .unwrap();
 
-        self.completion.indexer.list = match (
-            self.completion.curr_command(),
-            self.completion.curr_arg(),
-            self.completion.curr_value(),
-        ) {
+        self.indexer.list = match (self.curr_command(), self.curr_arg(), self.curr_value()) {
             (_, Some(&SliceData { hash_i: j, .. }), Some(&SliceData { hash_i: k, .. })) => (k, j),
             (Some(&SliceData { hash_i: i, .. }), None, Some(&SliceData { hash_i: k, .. })) => {
                 (k, i)
@@ -1449,59 +1665,110 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
             _ => (HashIndex::Invalid, HashIndex::Invalid),
         };
 
-        if self.completion.indexer.list.1 == HashIndex::Invalid {
-            self.completion.indexer.multiple = false;
+        if self.indexer.list.1 == HashIndex::Invalid {
+            self.indexer.multiple = false;
         }
 
-        let rec_data = [
-            self.completion.indexer.list.0,
-            self.completion.indexer.list.1,
-        ]
-        .map(|hash| {
-            (
-                Completion::index_recs(&self.completion.rec_list, hash),
-                hash,
-            )
-        });
+        true
+    }
+
+    #[inline]
+    fn curr_token(&self) -> &str {
+        &self.input.ending.token
+    }
+
+    #[inline]
+    fn open_quote(&self) -> Option<&(usize, char)> {
+        self.input.ending.open_quote.as_ref()
+    }
+
+    fn try_get_forward_arg_or_val(
+        &self,
+        line_trim_start: &str,
+        command_kind: &RecKind,
+    ) -> Option<SliceData> {
+        let (kind_match, nvals) = self.count_vals_in_slice(line_trim_start, command_kind);
+
+        if let Some(starting_token) = &kind_match {
+            let start_token_meta = Completion::index_recs(&self.rec_list, starting_token.hash_i);
+
+            if starting_token.hash_i == HashIndex::Invalid || nvals == 0 {
+                return kind_match;
+            }
+
+            if let RecData {
+                kind: RecKind::Value(range) | RecKind::UserDefined { range, .. },
+                end: false,
+                ..
+            } = start_token_meta
+            {
+                if range.contains(&nvals) {
+                    return None;
+                }
+            }
+        }
+
+        self.try_parse_token_from_end(line_trim_start, command_kind, Some(nvals))
+    }
+
+    /// Will panic if `self.completion.is_empty()`
+    fn set_default_recommendations_unchecked(&mut self) {
+        let commands = Self::get_commands(&self.rec_list);
+        self.recommendations = commands.recs.as_ref().expect("commands is not empty")
+            [..commands.unique_rec_end()]
+            .to_vec();
+        self.recommendations.push(HELP_STR);
+    }
+
+    /// Walks the given `line_trim_start` and updates the recommendations for the token at its end, see:
+    /// [`Self::walk`]
+    fn update(&mut self, line_trim_start: &str) {
+        if !self.walk(line_trim_start) {
+            return;
+        }
+
+        let rec_data = [self.indexer.list.0, self.indexer.list.1]
+            .map(|hash| (Completion::index_recs(&self.rec_list, hash), hash));
         let (rec_data_1, rec_data_2) = (rec_data[0].0, rec_data[1].0);
 
         let add_help = self.add_help(rec_data, line_trim_start);
 
         if self.curr_token().is_empty() {
             if let Some(recs) = rec_data_1.recs {
-                self.completion.recommendations = recs[..rec_data_1.unique_rec_end()].to_vec();
+                self.recommendations = recs[..rec_data_1.unique_rec_end()].to_vec();
             } else {
-                self.completion.recommendations.clear();
+                self.recommendations.clear();
             }
 
-            if self.completion.indexer.multiple {
+            if self.indexer.multiple {
                 if let Some(recs2) = rec_data_2.recs {
-                    let rec_len = self.completion.recommendations.len() as i8;
+                    let rec_len = self.recommendations.len() as i8;
                     let recs2 = &recs2[..rec_data_2.unique_rec_end()];
                     let rec_2_end = if add_help {
                         rec_len + recs2.len() as i8 + 1
                     } else {
                         rec_len + recs2.len() as i8
                     };
-                    self.completion.indexer.in_list_2 = (rec_len..rec_2_end).collect();
-                    self.completion.recommendations.extend(recs2);
+                    self.indexer.in_list_2 = (rec_len..rec_2_end).collect();
+                    self.recommendations.extend(recs2);
                 }
             }
             if add_help {
-                self.completion.recommendations.push(HELP_STR);
+                self.recommendations.push(HELP_STR);
             }
-            self.line.err = self.check_for_errors(line_trim_start);
             return;
         }
 
-        let input_lower = self.curr_token().trim_start_matches('-').to_lowercase();
+        let input_lower = unquote(self.curr_token())
+            .trim_start_matches('-')
+            .to_lowercase();
 
         let rec_1 = (!self.curr_token().starts_with('-')
             || matches!(rec_data_1.kind, RecKind::Argument(_)))
         .then(|| rec_data_1.recs.map(|recs| recs.iter()))
         .flatten();
 
-        let rec_2 = (self.completion.indexer.multiple
+        let rec_2 = (self.indexer.multiple
             && (!self.curr_token().starts_with('-')
                 || matches!(rec_data_2.kind, RecKind::Argument(_))))
         .then(|| rec_data_2.recs.map(|recs| recs.iter()))
@@ -1523,9 +1790,9 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
             b_starts.cmp(&a_starts)
         });
 
-        if self.completion.indexer.multiple {
+        if self.indexer.multiple {
             if let Some(recs2) = rec_data_2.recs {
-                self.completion.indexer.in_list_2 = recommendations
+                self.indexer.in_list_2 = recommendations
                     .iter()
                     .enumerate()
                     .filter(|&(_, rec)| recs2.contains(rec) || *rec == HELP_STR)
@@ -1534,26 +1801,728 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
             }
         }
 
-        self.completion.recommendations = recommendations;
-        self.line.err = self.check_for_errors(line_trim_start);
+        self.recommendations = recommendations;
+    }
+
+    #[inline]
+    fn add_help(&self, recs: [(&RecData, HashIndex); 2], line_trim_start: &str) -> bool {
+        let last_rec = recs[0].0;
+
+        last_rec.has_help
+            && (!matches!(
+                last_rec.kind,
+                RecKind::Value(_) | RecKind::UserDefined { .. }
+            ) || {
+                let trailing = self.trailing(line_trim_start);
+                self.kind_err_conditions(recs[0].1, self.curr_token(), trailing, line_trim_start)
+                    .is_none()
+            })
+            || recs[1].0.has_help
+                && (self.indexer.multiple
+                    || self
+                        .input
+                        .curr_value
+                        .is_some_and(|v| v.hash_i == HashIndex::Valid))
+    }
+
+    fn kind_err_conditions(
+        &self,
+        idx: HashIndex,
+        curr_token: &str,
+        trailing: &str,
+        line_trim_start: &str,
+    ) -> Option<DiagnosticKind> {
+        let rec = Completion::index_recs(&self.rec_list, idx);
+        let has_help = if idx == HashIndex::Valid {
+            self.curr_command()
+                .map(|cmd| Completion::index_recs(&self.rec_list, cmd.hash_i).has_help)
+                .expect("valid can only be set once a base entry is provided")
+        } else {
+            rec.has_help
+        };
+        let err = |condition: bool, kind| condition.then_some(kind);
+        match rec.kind {
+            RecKind::Argument(required) => {
+                match required
+                    .cmp(&(self.input.required_input_i.len() + !curr_token.is_empty() as usize))
+                {
+                    Ordering::Greater => Some(DiagnosticKind::MissingRequired),
+                    Ordering::Equal => None,
+                    Ordering::Less => {
+                        let unknown_arg =
+                            |condition| err(condition, DiagnosticKind::UnknownArgument);
+                        match strip_dashes(curr_token) {
+                            (0, Some(_)) => Some(DiagnosticKind::UnexpectedInput),
+                            (0..=2, None) => None,
+                            (1, Some(HELP_SHORT)) => {
+                                let parent_hash = self
+                                    .curr_command()
+                                    .expect("can only set arg if command is valid")
+                                    .hash_i;
+                                unknown_arg(
+                                    !Completion::index_recs(&self.rec_list, parent_hash).has_help,
+                                )
+                            }
+                            (1, Some(input)) if input.chars().nth(1).is_none() => unknown_arg(
+                                self.trimmed_arg_valid_i_unchecked(input, line_trim_start)
+                                    .is_none(),
+                            ),
+                            (1, Some(cluster)) => unknown_arg(
+                                self.short_cluster_valid_i_unchecked(cluster, line_trim_start)
+                                    .is_none(),
+                            ),
+                            (2, Some(input)) => {
+                                unknown_arg(!self.valid_rec_prefix(input, has_help))
+                            }
+                            _ => Some(DiagnosticKind::UnknownArgument),
+                        }
+                    }
+                }
+            }
+            _ if curr_token.starts_with('-') => err(
+                match strip_dashes(curr_token) {
+                    (0..=2, None) | (1, Some(HELP_SHORT)) | (2, Some(HELP_STR)) => !has_help,
+                    (2, Some(input)) => !self.valid_rec_prefix(input, has_help),
+                    _ => true,
+                },
+                DiagnosticKind::UnknownArgument,
+            ),
+            RecKind::Command => err(
+                !self.valid_rec_prefix(unquote(curr_token), has_help),
+                DiagnosticKind::UnknownCommand,
+            ),
+            // other `Value` and `UserDefined` errors do not need to be checked since `update_completion`
+            // will set `curr_value` to an invalid instance returning the error condition prior this fn call
+            RecKind::UserDefined { .. } | RecKind::Value(_) if trailing.is_empty() => {
+                Some(DiagnosticKind::MissingValue)
+            }
+            RecKind::UserDefined { parse_fn, .. } => err(
+                parse_fn.is_some_and(|valid| !valid(unquote(curr_token))),
+                DiagnosticKind::FailedParsingRule,
+            ),
+            RecKind::Value(_) => err(
+                !self.valid_rec_prefix(unquote(curr_token), has_help),
+                DiagnosticKind::InvalidValue,
+            ),
+            RecKind::ArgFlag | RecKind::Help | RecKind::Null => {
+                err(!trailing.is_empty(), DiagnosticKind::UnexpectedInput)
+            }
+        }
+    }
+
+    fn check_value_err(&self, line_trim_start: &str) -> Option<Diagnostic> {
+        let rec_list = [self.indexer.list.0, self.indexer.list.1];
+        let curr_token = self.curr_token();
+        let trailing = self.trailing(line_trim_start);
+        let diagnostic = |kind: DiagnosticKind| {
+            let end = line_trim_start.len();
+            let span = match kind {
+                DiagnosticKind::MissingRequired | DiagnosticKind::MissingValue => end..end,
+                DiagnosticKind::UnexpectedInput if curr_token.is_empty() => {
+                    self.trailing_range(line_trim_start)
+                }
+                _ if line_trim_start.ends_with(curr_token) => end - curr_token.len()..end,
+                _ => end..end,
+            };
+            Diagnostic::new(kind, span, line_trim_start)
+        };
+        let mut errs = [None, None];
+        for (err, hash) in errs.iter_mut().zip(rec_list) {
+            *err = self.kind_err_conditions(hash, curr_token, trailing, line_trim_start);
+            if !self.indexer.multiple {
+                return err.map(diagnostic);
+            }
+        }
+        if curr_token.starts_with('-')
+            && matches!(
+                Completion::index_recs(&self.rec_list, rec_list[1]).kind,
+                RecKind::Argument(_)
+            )
+        {
+            return errs[1].map(diagnostic);
+        }
+        errs[0].filter(|_| errs[1].is_some()).map(diagnostic)
+    }
+
+    /// Returns the kind of error an invalid `curr_value` produces
+    fn value_err_kind(&self, line_trim_start: &str) -> DiagnosticKind {
+        let (Some(parent), Some(value)) = (self.arg_or_cmd(), self.curr_value()) else {
+            return DiagnosticKind::UnexpectedInput;
+        };
+        let value_str = unquote(value.to_slice_unchecked(line_trim_start));
+        match Completion::index_recs(&self.rec_list, parent.hash_i).kind {
+            RecKind::Value(_) if !self.value_valid_unchecked(value_str, parent.hash_i) => {
+                DiagnosticKind::InvalidValue
+            }
+            RecKind::UserDefined {
+                parse_fn: Some(valid),
+                ..
+            } if !valid(value_str) => DiagnosticKind::FailedParsingRule,
+            _ => DiagnosticKind::UnexpectedInput,
+        }
+    }
+
+    /// Validates the current completion state of the given `line_trim_start`, returned spans index into
+    /// `line_trim_start`
+    fn find_errors(&self, line_trim_start: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = [
+            (self.curr_command(), DiagnosticKind::UnknownCommand),
+            (self.curr_arg(), DiagnosticKind::UnknownArgument),
+            (self.curr_value(), DiagnosticKind::InvalidValue),
+        ]
+        .into_iter()
+        .filter(|(slice, _)| slice.is_some_and_invalid())
+        .map(|(slice, kind)| {
+            let slice = slice.expect("filtered to `Some`");
+            let kind = match kind {
+                DiagnosticKind::InvalidValue => self.value_err_kind(line_trim_start),
+                kind => kind,
+            };
+            Diagnostic::new(kind, slice.byte_start..slice.byte_end_i(), line_trim_start)
+        })
+        .collect::<Vec<_>>();
+
+        if diagnostics.is_empty() {
+            diagnostics.extend(self.check_value_err(line_trim_start));
+        }
+        diagnostics
+    }
+
+    /// Classifies every token of the given `line` by replaying the completion walk one token at a time, so each
+    /// token is resolved exactly as it is for recommendations and diagnostics. Returned ranges index into
+    /// `line`. Kinds are cached until the line changes, see: [`Self::reclassify`]
+    fn classify(&mut self, line: &str) -> Vec<(Range<usize>, TokenKind)> {
+        let line = split_arg_assignments(line);
+        let offset = line.len() - line.trim_start().len();
+        let line_trim_start = &line[offset..];
+        if self.classified.line != line_trim_start {
+            self.reclassify(line_trim_start.to_string());
+        }
+
+        self.classified
+            .tokens
+            .iter()
+            .filter_map(|token| token.kind.map(|kind| (token.span.clone(), kind)))
+            .chain(self.classified.trailing.clone())
+            .map(|(span, kind)| (span.start + offset..span.end + offset, kind))
+            .collect()
+    }
+
+    /// Classifies the tokens of the given `line_trim_start` that follow the last token left unchanged since the
+    /// previous classification, resuming the walk from the state recorded after that token. The live completion
+    /// state is restored once every token is classified
+    fn reclassify(&mut self, line_trim_start: String) {
+        let spans = token_spans(&line_trim_start);
+        let ends = (0..spans.len())
+            .map(|i| {
+                spans
+                    .get(i + 1)
+                    .map_or(line_trim_start.len(), |next| next.start)
+            })
+            .collect::<Vec<_>>();
+
+        let Classified {
+            line: prev_line,
+            mut tokens,
+            ..
+        } = std::mem::take(&mut self.classified);
+        let unchanged = prev_line
+            .bytes()
+            .zip(line_trim_start.bytes())
+            .take_while(|(prev, new)| prev == new)
+            .count();
+        let reused = tokens
+            .iter()
+            .zip(spans.iter().zip(&ends))
+            .take_while(|(token, (span, &end))| {
+                token.span == **span && token.end == end && end <= unchanged
+            })
+            .count();
+        tokens.truncate(reused);
+
+        let (input, indexer) = tokens.last().map_or_else(Default::default, |token| {
+            (token.input.clone(), token.indexer.clone())
+        });
+        let live_input = std::mem::replace(&mut self.input, input);
+        let live_indexer = std::mem::replace(&mut self.indexer, indexer);
+
+        let mut trailing = None;
+        for (span, end) in spans.into_iter().zip(ends).skip(reused) {
+            if end == span.end {
+                trailing = self
+                    .classify_trailing(&line_trim_start, &span)
+                    .map(|kind| (span, kind));
+                break;
+            }
+            let kind = self.classify_token(&line_trim_start[..end], &span);
+            tokens.push(ClassifiedToken {
+                span,
+                end,
+                kind,
+                input: self.input.clone(),
+                indexer: self.indexer.clone(),
+            });
+        }
+
+        self.input = live_input;
+        self.indexer = live_indexer;
+        self.classified = Classified {
+            line: line_trim_start,
+            tokens,
+            trailing,
+        };
+    }
+
+    /// Walks the given `prefix` that ends with whitespace following the token at `span`, then classifies the
+    /// token by the slot of the completion state it was resolved into
+    fn classify_token(&mut self, prefix: &str, span: &Range<usize>) -> Option<TokenKind> {
+        // values are given to the argument or command that was current before the value was walked
+        let value_kind = self.value_token_kind();
+        self.walk(prefix);
+
+        let token = SliceData {
+            byte_start: span.start,
+            slice_len: span.len(),
+            hash_i: HashIndex::Invalid,
+        };
+        let resolved = |slice: Option<&SliceData>| {
+            slice
+                .filter(|&slice| *slice == token)
+                .map(|slice| slice.hash_i)
+        };
+        if let Some(hash_i) = resolved(self.curr_command()) {
+            return Some(valid_or_invalid(hash_i, TokenKind::Command));
+        }
+        if let Some(hash_i) = resolved(self.curr_arg()) {
+            return Some(valid_or_invalid(hash_i, TokenKind::Argument));
+        }
+        if let Some(hash_i) = resolved(self.curr_value()) {
+            return match hash_i {
+                HashIndex::Invalid => Some(TokenKind::Invalid),
+                _ => value_kind,
+            };
+        }
+        if let Some(hash_i) = resolved(self.input.last_resolved.as_ref()) {
+            return match hash_i {
+                HashIndex::Valid => value_kind,
+                _ => Some(TokenKind::Argument),
+            };
+        }
+        if self.input.required_input_i.contains(&span.end) {
+            return Some(TokenKind::UserInput);
+        }
+        if self
+            .find_errors(prefix)
+            .iter()
+            .any(|diagnostic| overlaps(&diagnostic.span(), span))
+        {
+            return Some(TokenKind::Invalid);
+        }
+        value_kind
+    }
+
+    /// Classifies the trailing token at `span` that is still being typed. Tokens that are not yet flagged by
+    /// diagnostics are classified as if they were completed, ones that would not resolve are `Partial`
+    fn classify_trailing(
+        &mut self,
+        line_trim_start: &str,
+        span: &Range<usize>,
+    ) -> Option<TokenKind> {
+        self.walk(line_trim_start);
+        if self.open_quote().is_some() {
+            return Some(TokenKind::Partial);
+        }
+        if self
+            .find_errors(line_trim_start)
+            .iter()
+            .any(|diagnostic| overlaps(&diagnostic.span(), span))
+        {
+            return Some(TokenKind::Invalid);
+        }
+        match self.classify_token(&format!("{line_trim_start} "), span) {
+            Some(TokenKind::Invalid) => Some(TokenKind::Partial),
+            kind => kind,
+        }
+    }
+}
+
+fn strip_dashes(str: &str) -> (usize, Option<&str>) {
+    let dashes = str.chars().take_while(|&c| c == '-').count();
+    (dashes, str.get(dashes..).filter(|&r| !r.is_empty()))
+}
+
+/// Rewrites `--arg=value` and `-a=value` tokens into their space separated form so the completion engine
+/// only has to reason about whitespace delimited tokens. `'='` and `' '` are both a single byte, so any
+/// byte index into the output is also a valid byte index into the given `line`
+fn split_arg_assignments(line: &str) -> Cow<'_, str> {
+    let mut assignments = Vec::new();
+    let mut open_quote = None;
+    let mut token_start = true;
+    let mut in_arg = false;
+
+    for (i, ch) in line.char_indices() {
+        if let Some(quote) = open_quote {
+            if ch == quote {
+                open_quote = None;
+            }
+            continue;
+        }
+        if ch.is_whitespace() {
+            (token_start, in_arg) = (true, false);
+            continue;
+        }
+        if std::mem::take(&mut token_start) {
+            in_arg = ch == '-';
+        }
+        if QUOTES.contains(&ch) {
+            open_quote = Some(ch);
+        } else if ch == '=' && std::mem::take(&mut in_arg) {
+            assignments.push(i);
+        }
+    }
+
+    if assignments.is_empty() {
+        return Cow::Borrowed(line);
+    }
+
+    Cow::Owned(
+        line.char_indices()
+            .map(|(i, ch)| if assignments.contains(&i) { ' ' } else { ch })
+            .collect(),
+    )
+}
+
+/// Splits the given `line` at the start of its last token, where `--arg=value` is seen as two tokens.
+/// Whitespace within quotes or escaped by a backslash does not end a token. The returned prefix includes
+/// the delimiter that separates the last token
+pub(super) fn split_last_token(line: &str) -> (&str, &str) {
+    let normalized = split_arg_assignments(line);
+    let mut open_quote = None;
+    let mut escaped = false;
+    let mut token_start = 0;
+
+    for (i, ch) in normalized.char_indices() {
+        if std::mem::take(&mut escaped) {
+            continue;
+        }
+        match open_quote {
+            Some(quote) if ch == quote => open_quote = None,
+            Some('\'') => (),
+            _ if ch == '\\' => escaped = true,
+            Some(_) => (),
+            None if QUOTES.contains(&ch) => open_quote = Some(ch),
+            None if ch.is_whitespace() => token_start = i + ch.len_utf8(),
+            None => (),
+        }
+    }
+
+    line.split_at(token_start)
+}
+
+fn valid_or_invalid(hash_i: HashIndex, kind: TokenKind) -> TokenKind {
+    if hash_i == HashIndex::Invalid {
+        TokenKind::Invalid
+    } else {
+        kind
+    }
+}
+
+#[inline]
+fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+/// Returns the byte range of every token of the given `line`. Whitespace within quotes or escaped by a
+/// backslash does not end a token, a token with an open quote extends to the end of the `line`
+fn token_spans(line: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut open_quote = None;
+    let mut escaped = false;
+    let mut token_start = None;
+
+    for (i, ch) in line.char_indices() {
+        if std::mem::take(&mut escaped) {
+            continue;
+        }
+        if open_quote.is_none() && ch.is_whitespace() {
+            if let Some(start) = token_start.take() {
+                spans.push(start..i);
+            }
+            continue;
+        }
+        token_start.get_or_insert(i);
+        match open_quote {
+            Some(quote) if ch == quote => open_quote = None,
+            Some('\'') => (),
+            _ if ch == '\\' => escaped = true,
+            Some(_) => (),
+            None if QUOTES.contains(&ch) => open_quote = Some(ch),
+            None => (),
+        }
+    }
+
+    if let Some(start) = token_start {
+        spans.push(start..line.len());
+    }
+    spans
+}
+
+/// Formats the placeholder of a value that accepts the given `range` of inputs, eg. `<NAME>` or `<NAME>...`
+fn value_placeholder(name: &str, range: &Range<usize>) -> String {
+    format!("<{name}>{}", if range.len() > 1 { "..." } else { "" })
+}
+
+/// Strips the surrounding quotes from a quoted token. Tokens that contain an open quote only have their
+/// leading quote removed
+fn unquote(token: &str) -> &str {
+    let Some(quote) = token.chars().next().filter(|c| QUOTES.contains(c)) else {
+        return token;
+    };
+    let inner = &token[quote.len_utf8()..];
+    inner.strip_suffix(quote).unwrap_or(inner)
+}
+
+/// Returns if the given `recommendation` has to be quoted to be parsed as a single token by `shellwords_split`
+pub(super) fn needs_quotes(recommendation: &str) -> bool {
+    recommendation.contains(|c: char| c.is_whitespace() || SHELL_METACHARS.contains(&c))
+}
+
+/// Quotes the given `recommendation` if it is required or the user already started the token with a quote.
+/// `open_quote` takes priority, otherwise single quotes are only used if the recommendation contains chars
+/// that would be escaped within double quotes
+pub(super) fn quote_recommendation(recommendation: &str, open_quote: Option<char>) -> Cow<'_, str> {
+    if open_quote.is_none() && !needs_quotes(recommendation) {
+        return Cow::Borrowed(recommendation);
+    }
+
+    let quote = open_quote.unwrap_or_else(|| {
+        if recommendation.contains(DOUBLE_QUOTE_ESCAPES) && !recommendation.contains('\'') {
+            '\''
+        } else {
+            '"'
+        }
+    });
+
+    let mut quoted = String::with_capacity(recommendation.len() + 2);
+    quoted.push(quote);
+    for ch in recommendation.chars() {
+        match quote {
+            '\'' if ch == '\'' => quoted.push_str("'\\''"),
+            '"' if DOUBLE_QUOTE_ESCAPES.contains(&ch) => {
+                quoted.push('\\');
+                quoted.push(ch);
+            }
+            _ => quoted.push(ch),
+        }
+    }
+    quoted.push(quote);
+    Cow::Owned(quoted)
+}
+
+impl<Ctx, W: Write> Repl<Ctx, W> {
+    #[inline]
+    fn curr_token(&self) -> &str {
+        self.completion.curr_token()
+    }
+
+    /// Builds the [`Signature`] of the current command. Returns `None` if the current command is not valid or
+    /// it does not take any inputs
+    pub(super) fn signature(&self) -> Option<Signature> {
+        let cmd = self
+            .completion
+            .curr_command()
+            .filter(|cmd| matches!(cmd.hash_i, HashIndex::Entry(_)))?;
+        let line = split_arg_assignments(&self.line.input);
+        let cmd_data = Completion::index_recs(&self.completion.rec_list, cmd.hash_i);
+
+        let mut segments = Vec::new();
+        let mut current = None;
+        match &cmd_data.kind {
+            &RecKind::Argument(required) => {
+                let curr_arg = self.completion.curr_arg().map(|arg| arg.hash_i);
+                let entered = self.completion.input.required_input_i.len();
+                if curr_arg.is_none() && entered < required && !self.curr_token().starts_with('-') {
+                    current = Some(entered);
+                }
+                segments.extend((0..required).map(|_| String::from("<input>")));
+
+                let args = cmd_data
+                    .recs
+                    .map_or(&[][..], |recs| &recs[..cmd_data.unique_rec_end()]);
+                for &arg in args {
+                    let Some(&arg_i) = self.completion.rec_map.get(arg) else {
+                        continue;
+                    };
+                    if curr_arg == Some(arg_i) {
+                        current = Some(segments.len());
+                    }
+                    segments.push(
+                        match &Completion::index_recs(&self.completion.rec_list, arg_i).kind {
+                            RecKind::Value(range) | RecKind::UserDefined { range, .. } => {
+                                format!(
+                                    "[--{arg} {}]",
+                                    value_placeholder(&arg.to_uppercase(), range)
+                                )
+                            }
+                            _ => format!("[--{arg}]"),
+                        },
+                    );
+                }
+            }
+            RecKind::Value(range) | RecKind::UserDefined { range, .. } => {
+                segments.push(value_placeholder("VALUE", range));
+                current = Some(0);
+            }
+            RecKind::ArgFlag | RecKind::Command | RecKind::Help | RecKind::Null => (),
+        }
+
+        if segments.is_empty() {
+            return None;
+        }
+
+        let mut text = String::from(unquote(cmd.to_slice_unchecked(line.trim_start())));
+        let mut current_range = None;
+        for (i, segment) in segments.iter().enumerate() {
+            text.push(' ');
+            if current == Some(i) {
+                current_range = Some(text.len()..text.len() + segment.len());
+            }
+            text.push_str(segment);
+        }
+
+        Some(Signature {
+            text,
+            current: current_range,
+        })
+    }
+
+    /// Validates the current state of the input line, returned spans are offset to index into `LineData.input`
+    fn check_for_errors(&self, line_trim_start: &str) -> Vec<Diagnostic> {
+        let offset = self.line.input.len() - line_trim_start.len();
+        self.completion
+            .find_errors(line_trim_start)
+            .into_iter()
+            .map(|diagnostic| diagnostic.offset_by(offset))
+            .collect()
+    }
+
+    /// Classifies every token of the input line, returned ranges index into `LineData.input`. See:
+    /// [`Completion::classify`]
+    pub(super) fn classify(&mut self) -> Vec<(Range<usize>, TokenKind)> {
+        self.completion.classify(&self.line.input)
+    }
+
+    /// Finds known variable names to suggest if a variable reference is being typed at the end of the input line
+    fn update_var_completion(&mut self) {
+        let trailing_var = (self.line.comp_enabled && self.line.vars_enabled)
+            .then(|| trailing_var_ref(&self.line.input))
+            .flatten();
+        let Some((start, braced, input)) = trailing_var else {
+            self.completion.var = None;
+            self.completion.env_vars = None;
+            return;
+        };
+
+        if self
+            .completion
+            .env_vars
+            .as_ref()
+            .map_or(true, |(var_start, _)| *var_start != start)
+        {
+            self.completion.env_vars = Some((start, env_var_names()));
+        }
+        let (_, env_names) = self.completion.env_vars.as_ref().expect("just set");
+        let recs = self.var_names(input, env_names);
+
+        self.completion.var = (!recs.is_empty()).then(|| VarCompletion {
+            start,
+            braced,
+            input: input.to_string(),
+            recs,
+            i: None,
+        });
+    }
+
+    /// Replaces the variable reference at the end of the input line with the next or previous known variable name
+    fn try_var_completion(&mut self, direction: Direction) -> io::Result<()> {
+        let var = self
+            .completion
+            .var
+            .as_mut()
+            .expect("caller ensures `var` is some");
+        let last = var.recs.len() - 1;
+        var.i = match (var.i, direction) {
+            (None, Direction::Next) => Some(0),
+            (None, Direction::Previous) => Some(last),
+            (Some(i), Direction::Next) => (i < last).then_some(i + 1),
+            (Some(i), Direction::Previous) => i.checked_sub(1),
+        };
+
+        let mut new_line = String::from(&self.line.input[..var.start]);
+        new_line.push('$');
+        if var.braced {
+            new_line.push('{');
+        }
+        match var.i {
+            Some(i) => {
+                new_line.push_str(&var.recs[i]);
+                if var.braced {
+                    new_line.push('}');
+                }
+            }
+            None => new_line.push_str(&var.input),
+        }
+
+        self.change_line_raw(new_line)?;
+        Ok(())
+    }
+
+    /// Updates the suggestions for the current user input
+    pub fn update_completion(&mut self) {
+        self.update_var_completion();
+        if !self.line.comp_enabled {
+            return;
+        }
+
+        let line = split_arg_assignments(&self.line.input);
+        let line_trim_start = line.trim_start();
+        if line_trim_start.is_empty() {
+            // `comp_enabled` can only be set when `!Completion.is_empty()` via checks in `enable_completion`
+            // and `ReplBuilder::build`. Making it safe to call `default_recommendations` here
+            self.completion.set_default_recommendations_unchecked();
+            self.line.diagnostics.clear();
+            self.completion.input.ending = LineEnd::default();
+            return;
+        }
+
+        self.completion.update(line_trim_start);
+        self.line.diagnostics = self.check_for_errors(line_trim_start);
     }
 
     /// Changes the current user input to either `Next` or `Previous` suggestion depending on the given direction
     pub fn try_completion(&mut self, direction: Direction) -> io::Result<()> {
+        // Completion state only tracks the end of the input line
+        if self.line.cursor_row_end.is_some() {
+            self.set_uneventful();
+            return Ok(());
+        }
+        if self.completion.var.is_some() {
+            return self.try_var_completion(direction);
+        }
+
+        let user_input = unquote(&self.completion.input.ending.token);
         if !self.line.comp_enabled
             || self.completion.recommendations.is_empty()
             || self.completion.last_key().is_some_and_invalid()
             || (self.completion.recommendations.len() == 1
                 && match self.completion.rec_data_from_index(0).kind {
                     RecKind::Value(_) => {
-                        self.curr_token() == self.completion.recommendations[0]
-                            && self.curr_token() != HELP_STR
+                        user_input == self.completion.recommendations[0] && user_input != HELP_STR
                     }
-                    RecKind::Argument(_) => self
-                        .curr_token()
+                    RecKind::Argument(_) => user_input
                         .strip_prefix("--")
                         .is_some_and(|user_input| user_input == self.completion.recommendations[0]),
-                    _ => self.curr_token() == self.completion.recommendations[0],
+                    _ => user_input == self.completion.recommendations[0],
                 })
         {
             self.set_uneventful();
@@ -1580,12 +2549,11 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
                     .rec_data_from_index(self.completion.indexer.recs)
                     .kind
                 {
-                    RecKind::Value(_) => self.curr_token() != next || self.curr_token() == HELP_STR,
-                    RecKind::Argument(_) => !self
-                        .curr_token()
+                    RecKind::Value(_) => user_input != next || user_input == HELP_STR,
+                    RecKind::Argument(_) => !user_input
                         .strip_prefix("--")
                         .is_some_and(|user_input| user_input == next),
-                    _ => self.curr_token() != next,
+                    _ => user_input != next,
                 } {
                     break next;
                 }
@@ -1593,25 +2561,25 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
         };
 
         let format_line = |rec_is_arg| {
-            self.line
-                .input
-                .rsplit_once(char::is_whitespace)
-                .map_or_else(
-                    || recommendation.to_string(),
-                    |(pre, _)| {
-                        format!(
-                            "{pre} {}{recommendation}",
-                            if rec_is_arg
-                                && !recommendation.is_empty()
-                                && self.completion.indexer.recs != USER_INPUT
-                            {
-                                "--"
-                            } else {
-                                ""
-                            }
-                        )
-                    },
-                )
+            let (pre, _) = split_last_token(&self.line.input);
+            if self.completion.indexer.recs == USER_INPUT {
+                return format!("{pre}{recommendation}");
+            }
+            // Keep the quote style the user started the token with
+            let user_quote = self
+                .curr_token()
+                .chars()
+                .next()
+                .filter(|c| QUOTES.contains(c));
+            format!(
+                "{pre}{}{}",
+                if rec_is_arg && !pre.is_empty() && !recommendation.is_empty() {
+                    "--"
+                } else {
+                    ""
+                },
+                quote_recommendation(recommendation, user_quote)
+            )
         };
 
         let kind = if recommendation == HELP_STR {
@@ -1632,11 +2600,16 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
                 .expect("guard clause covers `UserInput` and `Null`"),
         );
 
-        self.line.err = if self.completion.indexer.recs == USER_INPUT {
-            self.check_value_err(&new_line)
-        } else {
-            false
-        };
+        self.line.diagnostics.clear();
+        if self.completion.indexer.recs == USER_INPUT {
+            let new_line_trim_start = new_line.trim_start();
+            let offset = new_line.len() - new_line_trim_start.len();
+            self.line.diagnostics.extend(
+                self.completion
+                    .check_value_err(&split_arg_assignments(new_line_trim_start))
+                    .map(|diagnostic| diagnostic.offset_by(offset)),
+            );
+        }
 
         self.change_line_raw(new_line)?;
         Ok(())
@@ -1644,7 +2617,8 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
 
     /// Clears all state found by the completion module
     pub(super) fn reset_completion(&mut self) {
-        self.line.err = false;
+        self.line.diagnostics.clear();
+        self.completion.var = None;
         if self.completion.is_empty() {
             self.completion.input.ending = LineEnd::default();
             return;
@@ -1654,3 +2628,308 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
         self.completion.indexer = Indexer::default();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &CommandScheme = &CommandScheme::new(
+        RecData::new(RecKind::Command)
+            .with_recommendations(&["echo", "quit", "exit"])
+            .with_alias(&[(1, 2)]),
+        &[
+            InnerScheme::new(
+                RecData::new(RecKind::argument_with_no_required_inputs())
+                    .with_parent(Parent::Root)
+                    .with_recommendations(&["loud"])
+                    .with_short(&[(0, "l")]),
+                Some(&[InnerScheme::flag().with_parent(Parent::Entry("echo"))]),
+            ),
+            InnerScheme::end(Parent::Root),
+        ],
+    );
+
+    /// `verbose` is described as a flag under `build` and as a value under `test`
+    const DUPLICATE: &CommandScheme = &CommandScheme::new(
+        RecData::new(RecKind::Command).with_recommendations(&["build", "test"]),
+        &[
+            InnerScheme::new(
+                RecData::new(RecKind::argument_with_no_required_inputs())
+                    .with_parent(Parent::Root)
+                    .with_recommendations(&["verbose"]),
+                Some(&[InnerScheme::flag().with_parent(Parent::Entry("build"))]),
+            ),
+            InnerScheme::new(
+                RecData::new(RecKind::argument_with_no_required_inputs())
+                    .with_parent(Parent::Root)
+                    .with_recommendations(&["verbose"]),
+                Some(&[InnerScheme::new(
+                    RecData::new(RecKind::value_with_num_args(1))
+                        .with_parent(Parent::Entry("test"))
+                        .with_recommendations(&["low", "high"]),
+                    None,
+                )]),
+            ),
+        ],
+    );
+
+    /// The alias of `quit` points past the end of the recommendations
+    const BAD_INDEX: &CommandScheme = &CommandScheme::new(
+        RecData::new(RecKind::Command)
+            .with_recommendations(&["quit", "exit"])
+            .with_alias(&[(0, 5)]),
+        &[InnerScheme::end(Parent::Root)],
+    );
+
+    /// `echo` requires one user input and takes a `--case` value and a `--loud` flag, `roll` takes a `--sides`
+    /// input that must parse as a `u8`
+    const CLASSIFY: &CommandScheme = &CommandScheme::new(
+        RecData::new(RecKind::Command).with_recommendations(&["echo", "roll", "quit"]),
+        &[
+            InnerScheme::new(
+                RecData::new(RecKind::argument_with_required_user_defined(1))
+                    .with_parent(Parent::Root)
+                    .with_recommendations(&["case", "loud"])
+                    .with_short(&[(0, "c"), (1, "l")]),
+                Some(&[
+                    InnerScheme::new(
+                        RecData::new(RecKind::value_with_num_args(1))
+                            .with_parent(Parent::Entry("echo"))
+                            .with_recommendations(&["lower", "upper"]),
+                        None,
+                    ),
+                    InnerScheme::flag().with_parent(Parent::Entry("echo")),
+                ]),
+            ),
+            InnerScheme::new(
+                RecData::new(RecKind::argument_with_no_required_inputs())
+                    .with_parent(Parent::Root)
+                    .with_recommendations(&["sides"]),
+                Some(&[InnerScheme::user_defined(1)
+                    .with_parent(Parent::Entry("roll"))
+                    .with_parsing_rule(|sides| sides.parse::<u8>().is_ok())]),
+            ),
+            InnerScheme::end(Parent::Root),
+        ],
+    );
+
+    fn classify(completion: &mut Completion, line: &'static str) -> Vec<(&'static str, TokenKind)> {
+        completion.update(split_arg_assignments(line).trim_start());
+        completion
+            .classify(line)
+            .into_iter()
+            .map(|(range, kind)| (&line[range], kind))
+            .collect()
+    }
+
+    fn error_kinds(scheme: &'static CommandScheme) -> Vec<(Vec<&'static str>, SchemeErrorKind)> {
+        scheme
+            .validate()
+            .expect_err("scheme is ill formed")
+            .into_iter()
+            .map(|err| (err.path().to_vec(), err.kind().clone()))
+            .collect()
+    }
+
+    #[test]
+    fn valid_scheme() {
+        assert_eq!(VALID.validate(), Ok(()));
+        VALID.assert_valid();
+    }
+
+    #[test]
+    fn duplicate_entry() {
+        assert_eq!(
+            error_kinds(DUPLICATE),
+            [(
+                vec!["test", "verbose"],
+                SchemeErrorKind::DuplicateEntry("verbose")
+            )]
+        );
+    }
+
+    #[test]
+    fn invalid_alias_index() {
+        assert_eq!(
+            error_kinds(BAD_INDEX),
+            [(vec!["quit"], SchemeErrorKind::InvalidAlias(5))]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "alias index: 5, does not point to a recommendation")]
+    fn assert_valid_panics() {
+        BAD_INDEX.assert_valid();
+    }
+
+    #[test]
+    fn from_valid_scheme() {
+        let completion = Completion::from(VALID);
+        assert_eq!(completion.recommendations, ["echo", "quit", HELP_STR]);
+    }
+
+    #[test]
+    #[should_panic(expected = "alias index: 5, does not point to a recommendation")]
+    fn from_ill_formed_scheme_panics() {
+        let _ = Completion::from(BAD_INDEX);
+    }
+
+    #[test]
+    fn plain_recommendations_are_not_quoted() {
+        assert_eq!(quote_recommendation("build", None), "build");
+        assert!(matches!(
+            quote_recommendation("build", None),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn quote_escaping() {
+        let cases = [
+            ("hello world", None, r#""hello world""#),
+            ("$HOME dir", None, "'$HOME dir'"),
+            (r"C:\temp", None, r"'C:\temp'"),
+            ("it's", None, r#""it's""#),
+            (r#"say "hi" it's"#, None, r#""say \"hi\" it's""#),
+            ("it's", Some('\''), r"'it'\''s'"),
+            ("a $b", Some('"'), r#""a \$b""#),
+            ("build", Some('"'), r#""build""#),
+        ];
+
+        for (recommendation, open_quote, expected) in cases {
+            let quoted = quote_recommendation(recommendation, open_quote);
+            assert_eq!(quoted, expected, "quoting: {recommendation:?}");
+            assert_eq!(
+                shellwords::split(&quoted).unwrap(),
+                [recommendation],
+                "round trip of: {quoted}"
+            );
+        }
+    }
+
+    #[test]
+    fn unquote_tokens() {
+        assert_eq!(unquote("\"hello world\""), "hello world");
+        assert_eq!(unquote("'hello"), "hello");
+        assert_eq!(unquote("hello'"), "hello'");
+        assert_eq!(unquote("\"'"), "'");
+    }
+
+    #[test]
+    fn last_token_respects_quotes() {
+        assert_eq!(split_last_token("echo hello"), ("echo ", "hello"));
+        assert_eq!(split_last_token("echo \"a b"), ("echo ", "\"a b"));
+        assert_eq!(split_last_token("echo 'a \" b' c"), ("echo 'a \" b' ", "c"));
+        assert_eq!(split_last_token(r"echo a\ b"), ("echo ", r"a\ b"));
+        assert_eq!(
+            split_last_token("echo --file=\"a b"),
+            ("echo --file=", "\"a b")
+        );
+        assert_eq!(split_last_token("echo "), ("echo ", ""));
+    }
+
+    #[test]
+    fn token_kinds() {
+        use TokenKind::*;
+
+        let cases: [(&str, &[(&str, TokenKind)]); 9] = [
+            (
+                "echo hi --case upper",
+                &[
+                    ("echo", Command),
+                    ("hi", UserInput),
+                    ("--case", Argument),
+                    ("upper", Value),
+                ],
+            ),
+            (
+                "  echo -l hi",
+                &[("echo", Command), ("-l", Argument), ("hi", UserInput)],
+            ),
+            (
+                "echo hi --case=lower",
+                &[
+                    ("echo", Command),
+                    ("hi", UserInput),
+                    ("--case", Argument),
+                    ("lower", Value),
+                ],
+            ),
+            (
+                "echo hi --case shout ",
+                &[
+                    ("echo", Command),
+                    ("hi", UserInput),
+                    ("--case", Argument),
+                    ("shout", Invalid),
+                ],
+            ),
+            (
+                "echo hi --bogus ",
+                &[("echo", Command), ("hi", UserInput), ("--bogus", Invalid)],
+            ),
+            (
+                "roll --sides 300 ",
+                &[("roll", Command), ("--sides", Argument), ("300", Invalid)],
+            ),
+            ("bogus hi", &[("bogus", Invalid)]),
+            ("ec", &[("ec", Partial)]),
+            (
+                "echo hi --case up",
+                &[
+                    ("echo", Command),
+                    ("hi", UserInput),
+                    ("--case", Argument),
+                    ("up", Partial),
+                ],
+            ),
+        ];
+
+        for (line, expected) in cases {
+            let mut completion = Completion::from(CLASSIFY);
+            assert_eq!(
+                classify(&mut completion, line),
+                expected,
+                "classifying: {line:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn unchanged_tokens_are_not_walked_again() {
+        let mut completion = Completion::from(CLASSIFY);
+        classify(&mut completion, "echo hi --case u");
+
+        // a kind that can not be produced by a walk marks the token as reused
+        completion.classified.tokens[1].kind = Some(TokenKind::Partial);
+        assert_eq!(
+            classify(&mut completion, "echo hi --case upper"),
+            [
+                ("echo", TokenKind::Command),
+                ("hi", TokenKind::Partial),
+                ("--case", TokenKind::Argument),
+                ("upper", TokenKind::Value)
+            ]
+        );
+
+        // editing `hi` invalidates every token after it
+        assert_eq!(
+            classify(&mut completion, "echo ho --case upper"),
+            [
+                ("echo", TokenKind::Command),
+                ("ho", TokenKind::UserInput),
+                ("--case", TokenKind::Argument),
+                ("upper", TokenKind::Value)
+            ]
+        );
+    }
+
+    #[test]
+    fn classification_restores_live_state() {
+        let mut completion = Completion::from(CLASSIFY);
+        completion.walk("roll ");
+        completion.classify("echo hi --case upper");
+        assert_eq!(completion.curr_command().map(|cmd| cmd.slice_len), Some(4));
+        assert!(completion.curr_arg().is_none());
+    }
+}
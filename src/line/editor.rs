@@ -0,0 +1,197 @@
+use crate::line::{EventLoop, Repl};
+
+use std::{
+    fs::OpenOptions,
+    io::{self, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crossterm::{
+    cursor, execute,
+    terminal::{Clear, ClearType::FromCursorDown, EndSynchronizedUpdate},
+    QueueableCommand,
+};
+use shellwords::split as shellwords_split;
+
+#[cfg(windows)]
+const DEFAULT_EDITOR: &str = "notepad";
+#[cfg(not(windows))]
+const DEFAULT_EDITOR: &str = "vi";
+
+const TEMP_FILE_ATTEMPTS: u32 = 16;
+
+/// Returns the program and arguments of the editor set via the `VISUAL` or `EDITOR` environment variables,
+/// falls back to the platforms default editor
+fn editor_command() -> Vec<String> {
+    ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find_map(|editor| {
+            shellwords_split(&editor)
+                .ok()
+                .filter(|args| !args.is_empty())
+        })
+        .unwrap_or_else(|| vec![String::from(DEFAULT_EDITOR)])
+}
+
+/// Creates a uniquely named file within the temp directory that contains the given `input`. The file must
+/// not exist beforehand, so an existing file or symlink at a guessed path is never written through
+fn create_temp_file(input: &str) -> io::Result<PathBuf> {
+    let dir = std::env::temp_dir();
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.subsec_nanos());
+
+    for attempt in 0..TEMP_FILE_ATTEMPTS {
+        let path = dir.join(format!(
+            "repl-oxide-{}-{:08x}",
+            std::process::id(),
+            seed.wrapping_add(attempt.wrapping_mul(0x9E37_79B9))
+        ));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        match options.open(&path) {
+            Ok(mut file) => {
+                if let Err(err) = file.write_all(input.as_bytes()) {
+                    let _ = std::fs::remove_file(&path);
+                    return Err(err);
+                }
+                return Ok(path);
+            }
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Err(io::Error::new(
+        ErrorKind::AlreadyExists,
+        "Could not create a unique temporary file",
+    ))
+}
+
+/// Writes the given `input` to a new temporary file and opens it within the given `editor`, see: [`run_editor`].
+/// The file is removed once the editor exits
+fn edit_file(editor: &[String], input: &str) -> io::Result<Option<String>> {
+    let path = create_temp_file(input)?;
+    let edited = run_editor(editor, &path);
+    let _ = std::fs::remove_file(&path);
+    edited
+}
+
+/// Opens the file at `path` within the `editor`, its program followed by its arguments, see: [`editor_command`].
+/// Returns the contents of the file once the editor exits, or `None` if the editor exited unsuccessfully
+fn run_editor(editor: &[String], path: &Path) -> io::Result<Option<String>> {
+    let status = Command::new(&editor[0])
+        .args(&editor[1..])
+        .arg(path)
+        .status()
+        .map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                format!("Could not launch `{}`: {err}", editor[0]),
+            )
+        })?;
+
+    if !status.success() {
+        return Ok(None);
+    }
+
+    // Editors commonly append a new line to the end of the file
+    let edited = std::fs::read_to_string(path)?.replace("\r\n", "\n");
+    Ok(Some(String::from(edited.trim_end_matches('\n'))))
+}
+
+impl<Ctx, W: Write> Repl<Ctx, W> {
+    /// Returns if lines edited within an external editor are submitted once the editor exits
+    #[inline]
+    pub fn editor_auto_submit_enabled(&self) -> bool {
+        self.line.editor_submit
+    }
+
+    /// Submits lines edited within an external editor once the editor exits
+    #[inline]
+    pub fn enable_editor_auto_submit(&mut self) {
+        self.line.editor_submit = true
+    }
+
+    /// Lines edited within an external editor are placed back into the input line
+    #[inline]
+    pub fn disable_editor_auto_submit(&mut self) {
+        self.line.editor_submit = false
+    }
+
+    /// Suspends the repl and opens the current input line within the editor set via the `VISUAL` or `EDITOR`
+    /// environment variables. Once the editor exits the edited line replaces the current input line, if
+    /// `submit` is set the edited line is submitted instead. Edits are discarded if the editor exits
+    /// unsuccessfully. Bound to <kbd>Ctrl</kbd> + <kbd>X</kbd>, <kbd>Ctrl</kbd> + <kbd>E</kbd>
+    ///
+    /// Raw mode is disabled while the editor is open. The editor is given the processes standard streams, so
+    /// this method is only useful when the repl is writing to the terminal
+    pub fn edit_in_editor(&mut self, submit: bool) -> io::Result<EventLoop<Ctx, W>> {
+        if !self.cursor_at_start {
+            self.move_to_beginning(self.cursor_len())?;
+        }
+        self.term.queue(Clear(FromCursorDown))?;
+        execute!(self.term, cursor::Show, EndSynchronizedUpdate)?;
+
+        crossterm::terminal::disable_raw_mode()?;
+        let edited = edit_file(&editor_command(), self.input());
+        crossterm::terminal::enable_raw_mode()?;
+
+        // The editor may leave the cursor anywhere, the line must be redrawn from scratch
+        self.command_entered = true;
+
+        let line = match edited {
+            Ok(Some(line)) => line,
+            Ok(None) => return Ok(EventLoop::Continue),
+            Err(err) => {
+                self.eprintln(err)?;
+                return Ok(EventLoop::Continue);
+            }
+        };
+
+        if !submit || line.trim().is_empty() {
+            self.change_line(line)?;
+            return Ok(EventLoop::Continue);
+        }
+        self.submit_line(line)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_file_with_fake_editor() {
+        let script =
+            create_temp_file("printf '%s --edited\\n' \"$(cat \"$1\")\" > \"$1\"\n").unwrap();
+
+        let editor = [String::from("sh"), script.display().to_string()];
+        let edited = edit_file(&editor, "echo hi");
+        let failed = edit_file(&[String::from("false")], "echo hi");
+        let _ = std::fs::remove_file(&script);
+
+        assert_eq!(edited.unwrap().as_deref(), Some("echo hi --edited"));
+        assert_eq!(failed.unwrap(), None);
+    }
+
+    #[test]
+    fn temp_files_are_unique() {
+        let first = create_temp_file("first").unwrap();
+        let second = create_temp_file("second").unwrap();
+        let contents = [
+            std::fs::read_to_string(&first),
+            std::fs::read_to_string(&second),
+        ];
+        let _ = std::fs::remove_file(&first);
+        let _ = std::fs::remove_file(&second);
+
+        assert_ne!(first, second);
+        assert_eq!(contents.map(Result::unwrap), ["first", "second"]);
+    }
+}
@@ -0,0 +1,213 @@
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Returns the number of columns a grapheme cluster occupies, terminals display every cluster with a width
+/// of at most 2
+#[inline]
+pub(super) fn grapheme_width(grapheme: &str) -> u16 {
+    grapheme.width().min(2) as u16
+}
+
+/// Returns the number of columns the given `str` occupies when displayed on a single row
+pub(super) fn display_width(str: &str) -> u16 {
+    str.graphemes(true)
+        .map(grapheme_width)
+        .fold(0, u16::saturating_add)
+}
+
+/// Returns the number of columns the given `str` occupies when displayed on a single row, escape sequences
+/// occupy no columns. See: [`ansi_segments`]
+pub(super) fn ansi_display_width(str: &str) -> u16 {
+    ansi_segments(str)
+        .into_iter()
+        .filter(|(_, escape)| !escape)
+        .map(|(range, _)| display_width(&str[range]))
+        .fold(0, u16::saturating_add)
+}
+
+/// Splits the given `str` into its escape sequences and displayed text, returning the byte range of each
+/// segment and if the segment is an escape sequence. CSI and OSC sequences are parsed, any other escape
+/// sequence is assumed to be two characters long
+pub(super) fn ansi_segments(str: &str) -> Vec<(Range<usize>, bool)> {
+    let mut segments = Vec::new();
+    let mut text_start = 0;
+
+    let mut chars = str.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        if ch != '\x1b' {
+            continue;
+        }
+        if text_start != i {
+            segments.push((text_start..i, false));
+        }
+        if chars.next_if(|&(_, ch)| ch == '[').is_some() {
+            // CSI sequences are terminated by a byte within the range '@'..='~'
+            while chars
+                .next()
+                .is_some_and(|(_, ch)| !('@'..='~').contains(&ch))
+            {}
+        } else if chars.next_if(|&(_, ch)| ch == ']').is_some() {
+            // OSC sequences, eg. hyperlinks, are terminated by BEL or ST (`ESC \`)
+            while let Some((_, ch)) = chars.next() {
+                if ch == '\x07' || ch == '\x1b' && chars.next_if(|&(_, ch)| ch == '\\').is_some() {
+                    break;
+                }
+            }
+        } else {
+            chars.next();
+        }
+        text_start = chars.peek().map_or(str.len(), |&(i, _)| i);
+        segments.push((i..text_start, true));
+    }
+
+    if text_start != str.len() {
+        segments.push((text_start..str.len(), false));
+    }
+    segments
+}
+
+/// Lays out the given `text` on a terminal that is `columns` wide, starting at the offset `start`. Returns the
+/// offset directly after the laid out text
+///
+/// Offsets are measured in cells from the start of the line, so `offset / columns` is the row and
+/// `offset % columns` is the column. A wide grapheme that does not fit within the remaining columns of a row
+/// is wrapped to the next row by the terminal, the skipped cells are included in the returned offset
+pub(super) fn wrapped_len(start: u16, text: &str, columns: u16) -> u16 {
+    text.graphemes(true).fold(start, |offset, grapheme| {
+        let width = grapheme_width(grapheme);
+        let column = offset % columns;
+        let offset = if width <= columns && column + width > columns {
+            offset.saturating_add(columns - column)
+        } else {
+            offset
+        };
+        offset.saturating_add(width)
+    })
+}
+
+/// Lays out the rows of the given multi-line `text`, see: [`wrapped_len`]. Every row after the first starts on
+/// a new terminal row and is preceded by a continuation prompt that is `continuation_len` cells wide
+pub(super) fn multi_line_len(start: u16, text: &str, columns: u16, continuation_len: u16) -> u16 {
+    let mut rows = text.split('\n');
+    let first = rows.next().expect("split always yields an item");
+    rows.fold(wrapped_len(start, first, columns), |offset, row| {
+        // An offset on a row boundary is already placed at the start of the next row
+        let row_start = offset.div_ceil(columns).saturating_mul(columns);
+        wrapped_len(row_start.saturating_add(continuation_len), row, columns)
+    })
+}
+
+/// Returns the number of cells the given `lines` occupy when each one starts on a new row of a terminal that is
+/// `columns` wide. Every line takes up at least one full row
+pub(super) fn rows_len<'a>(lines: impl IntoIterator<Item = &'a str>, columns: u16) -> u16 {
+    lines
+        .into_iter()
+        .map(|line| {
+            wrapped_len(0, line, columns)
+                .div_ceil(columns)
+                .max(1)
+                .saturating_mul(columns)
+        })
+        .fold(0, u16::saturating_add)
+}
+
+/// Returns the column a right prompt that is `width` cells wide starts at, on a terminal that is `columns` wide.
+/// Returns `None` when the text ending at the offset `line_end` would collide with it
+///
+/// A gap is left after the text and the last column is kept empty so the terminal never wraps the row
+pub(super) fn right_prompt_start(line_end: u16, width: u16, columns: u16) -> Option<u16> {
+    columns
+        .checked_sub(width.saturating_add(1))
+        .filter(|&start| line_end < start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CJK: &str = "界";
+    const ZWJ_FAMILY: &str = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+    const COMBINING_E: &str = "e\u{301}";
+
+    #[test]
+    fn display_width_by_grapheme() {
+        assert_eq!(display_width(CJK), 2);
+        assert_eq!(display_width(ZWJ_FAMILY), 2);
+        assert_eq!(display_width(COMBINING_E), 1);
+        assert_eq!(
+            display_width(&format!("ab{CJK}{ZWJ_FAMILY}{COMBINING_E}")),
+            7
+        );
+    }
+
+    #[test]
+    fn osc_sequences_occupy_no_columns() {
+        let bel = "\x1b]8;;https://example.com\x07link\x1b]8;;\x07";
+        let st = "\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\";
+        for link in [bel, st] {
+            let text = ansi_segments(link)
+                .into_iter()
+                .filter(|(_, escape)| !escape)
+                .map(|(range, _)| &link[range])
+                .collect::<Vec<_>>();
+            assert_eq!(text, ["link"]);
+            assert_eq!(ansi_display_width(link), 4);
+        }
+        assert_eq!(ansi_display_width("\x1b[1;31mred\x1b[0m"), 3);
+    }
+
+    #[test]
+    fn wide_grapheme_straddling_last_column_wraps() {
+        // the last column of the first row is skipped
+        assert_eq!(wrapped_len(9, CJK, 10), 12);
+        assert_eq!(wrapped_len(9, ZWJ_FAMILY, 10), 12);
+        // exactly fills the first row
+        assert_eq!(wrapped_len(8, CJK, 10), 10);
+        assert_eq!(wrapped_len(8, ZWJ_FAMILY, 10), 10);
+    }
+
+    #[test]
+    fn combining_accent_in_last_column() {
+        assert_eq!(wrapped_len(9, COMBINING_E, 10), 10);
+        assert_eq!(wrapped_len(9, &format!("{COMBINING_E}x"), 10), 11);
+    }
+
+    #[test]
+    fn multi_line_rows() {
+        assert_eq!(multi_line_len(8, &format!("ab\n{CJK}"), 10, 2), 14);
+        // the wide grapheme straddles the last column of a continuation row
+        assert_eq!(
+            multi_line_len(0, &format!("abcdefghi\n1234567{CJK}"), 10, 2),
+            22
+        );
+        // a combining accent in the last column fills the row without wrapping
+        assert_eq!(
+            multi_line_len(0, &format!("abcdefghi{COMBINING_E}\nx"), 10, 2),
+            13
+        );
+    }
+
+    #[test]
+    fn right_prompt_hides_on_overlap() {
+        assert_eq!(right_prompt_start(0, 4, 20), Some(15));
+        // one cell gap between the text and the right prompt
+        assert_eq!(right_prompt_start(14, 4, 20), Some(15));
+        assert_eq!(right_prompt_start(15, 4, 20), None);
+        // text that wrapped past the first row
+        assert_eq!(right_prompt_start(25, 4, 20), None);
+        // wider than the terminal
+        assert_eq!(right_prompt_start(0, 20, 20), None);
+    }
+
+    #[test]
+    fn lines_take_up_full_rows() {
+        assert_eq!(rows_len([], 10), 0);
+        assert_eq!(rows_len(["", "abc"], 10), 20);
+        // exactly fills one row, then wraps onto a second
+        assert_eq!(rows_len(["abcdefghij", "abcdefghijk"], 10), 30);
+        // the wide grapheme is wrapped past the last column
+        assert_eq!(rows_len([format!("abcdefghi{CJK}").as_str()], 10), 20);
+    }
+}
@@ -0,0 +1,353 @@
+use crate::line::{
+    print::{print_lines, BackgroundMsg},
+    style::ansi_code::RESET,
+    Repl,
+};
+
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Formats the given `time` as `HH:MM:SS` in UTC
+pub(super) fn format_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Severity of a [`LogMessage`], ordered from least to most severe
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    /// Returns the name of the level in uppercase
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
+/// Minimum [`Level`] of [`LogMessage`]s that are displayed, shared between the repl and anything that discards
+/// messages before they are sent to the repl, eg. `ReplTracingLayer`. Clones refer to the same level, so changes
+/// made via [`Repl::set_log_level`] are observed by every clone. See: [`Repl::shared_log_level`]
+#[derive(Debug, Clone)]
+pub struct SharedLevel(Arc<AtomicU8>);
+
+impl Default for SharedLevel {
+    fn default() -> Self {
+        Self::new(Level::default())
+    }
+}
+
+impl SharedLevel {
+    /// Creates a new minimum level, the level used by the repl is returned by [`Repl::shared_log_level`]
+    pub fn new(level: Level) -> Self {
+        SharedLevel(Arc::new(AtomicU8::new(level as u8)))
+    }
+
+    /// Returns the current minimum level
+    #[inline]
+    pub fn get(&self) -> Level {
+        match self.0.load(Ordering::Relaxed) {
+            0 => Level::Trace,
+            1 => Level::Debug,
+            2 => Level::Info,
+            3 => Level::Warn,
+            _ => Level::Error,
+        }
+    }
+
+    /// Sets the minimum level of every clone of this shared level
+    #[inline]
+    pub fn set(&self, level: Level) {
+        self.0.store(level as u8, Ordering::Relaxed)
+    }
+
+    /// Returns if messages of the given `level` are displayed, that is `level` is at least the minimum level
+    #[inline]
+    pub fn enabled(&self, level: Level) -> bool {
+        level >= self.get()
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// Error returned when parsing a [`Level`] from a string that does not name a level
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLevelError(String);
+
+impl Display for ParseLevelError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid log level '{}', expected one of: trace, debug, info, warn, error",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseLevelError {}
+
+impl FromStr for Level {
+    type Err = ParseLevelError;
+
+    /// Parses the name of a level, ignoring case. Accepts `warning` as an alias of `warn`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "trace" => Ok(Level::Trace),
+            "debug" => Ok(Level::Debug),
+            "info" => Ok(Level::Info),
+            "warn" | "warning" => Ok(Level::Warn),
+            "error" => Ok(Level::Error),
+            _ => Err(ParseLevelError(String::from(s))),
+        }
+    }
+}
+
+/// Structured message that is printed above the input line with a prefix and color set by its [`Level`]
+///
+/// `LogMessage`s can be sent through the channel returned by [`Repl::spawn`] or printed directly via
+/// [`Repl::log`]. Messages below the repl's minimum level are discarded, see: [`Repl::set_log_level`].
+/// The colors of each level are set via the [`Theme`].
+///
+/// # Example
+///
+/// ```ignore
+/// let (repl_task, message_sender) = repl.spawn(ctx);
+///
+/// message_sender
+///     .send(LogMessage::warn("Connection lost, retrying").with_source("net").with_timestamp())
+///     .await?;
+/// ```
+///
+/// [`Repl::spawn`]: crate::line::Repl::spawn
+/// [`Theme`]: crate::line::theme::Theme
+#[derive(Debug, Clone)]
+pub struct LogMessage {
+    level: Level,
+    source: Option<String>,
+    timestamp: Option<SystemTime>,
+    message: String,
+}
+
+impl LogMessage {
+    /// Creates a new message with the given `level`
+    pub fn new<D: Display>(level: Level, message: D) -> Self {
+        LogMessage {
+            level,
+            source: None,
+            timestamp: None,
+            message: message.to_string(),
+        }
+    }
+
+    /// Creates a new message with the level [`Level::Trace`]
+    pub fn trace<D: Display>(message: D) -> Self {
+        Self::new(Level::Trace, message)
+    }
+
+    /// Creates a new message with the level [`Level::Debug`]
+    pub fn debug<D: Display>(message: D) -> Self {
+        Self::new(Level::Debug, message)
+    }
+
+    /// Creates a new message with the level [`Level::Info`]
+    pub fn info<D: Display>(message: D) -> Self {
+        Self::new(Level::Info, message)
+    }
+
+    /// Creates a new message with the level [`Level::Warn`]
+    pub fn warn<D: Display>(message: D) -> Self {
+        Self::new(Level::Warn, message)
+    }
+
+    /// Creates a new message with the level [`Level::Error`]
+    pub fn error<D: Display>(message: D) -> Self {
+        Self::new(Level::Error, message)
+    }
+
+    /// Sets the source of the message, eg. the name of the task that sent it. Displayed after the level
+    pub fn with_source<S: Into<String>>(mut self, source: S) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Timestamps the message with the current time. Displayed in UTC before the level
+    pub fn with_timestamp(mut self) -> Self {
+        self.timestamp = Some(SystemTime::now());
+        self
+    }
+
+    #[inline]
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    #[inline]
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    #[inline]
+    pub fn timestamp(&self) -> Option<SystemTime> {
+        self.timestamp
+    }
+
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Formats the message, the level is wrapped in the given `style`
+    fn format(&self, style: Option<&str>) -> String {
+        let mut formatted = String::new();
+        if let Some(timestamp) = self.timestamp {
+            formatted.push_str(&format_timestamp(timestamp));
+            formatted.push(' ');
+        }
+        match style {
+            Some(style) => formatted.push_str(&format!("{style}{:>5}{RESET}", self.level)),
+            None => formatted.push_str(&format!("{:>5}", self.level)),
+        }
+        if let Some(ref source) = self.source {
+            formatted.push(' ');
+            formatted.push_str(source);
+            formatted.push(':');
+        }
+        formatted.push(' ');
+        formatted.push_str(&self.message);
+        formatted
+    }
+}
+
+impl BackgroundMsg for LogMessage {
+    fn print<Ctx, W: Write>(self, repl: &mut Repl<Ctx, W>) -> io::Result<()> {
+        repl.log(&self)
+    }
+}
+
+impl<Ctx, W: Write> Repl<Ctx, W> {
+    /// Returns the minimum level of [`LogMessage`]s that are displayed
+    #[inline]
+    pub fn log_level(&self) -> Level {
+        self.log_level.get()
+    }
+
+    /// Sets the minimum level of [`LogMessage`]s that are displayed, eg. from a `loglevel` command. Defaults to
+    /// [`Level::Info`]
+    #[inline]
+    pub fn set_log_level(&mut self, level: Level) {
+        self.log_level.set(level)
+    }
+
+    /// Returns a handle to the repl's minimum log level that follows every call to [`Self::set_log_level`], eg.
+    /// to discard messages before they are sent. See: [`SharedLevel`]
+    #[inline]
+    pub fn shared_log_level(&self) -> SharedLevel {
+        self.log_level.clone()
+    }
+
+    /// Prints the given `message` if its level is at least the repl's minimum level, see: [`LogMessage`]
+    pub fn log(&mut self, message: &LogMessage) -> io::Result<()> {
+        if !self.log_level.enabled(message.level) {
+            return Ok(());
+        }
+        let palette = &self.line.palette;
+        let style = self.line.style_enabled.then(|| match message.level {
+            Level::Trace => palette.log_trace.clone(),
+            Level::Debug => palette.log_debug.clone(),
+            Level::Info => palette.log_info.clone(),
+            Level::Warn => palette.log_warn.clone(),
+            Level::Error => palette.log_error.clone(),
+        });
+        let formatted = message.format(style.as_deref());
+        self.record_output(&formatted)?;
+        print_lines(&mut self.background_writer()?, formatted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn parse_level() {
+        assert_eq!("trace".parse(), Ok(Level::Trace));
+        assert_eq!("Debug".parse(), Ok(Level::Debug));
+        assert_eq!("INFO".parse(), Ok(Level::Info));
+        assert_eq!("warn".parse(), Ok(Level::Warn));
+        assert_eq!("Warning".parse(), Ok(Level::Warn));
+        assert_eq!("error".parse(), Ok(Level::Error));
+        assert_eq!(
+            "fatal".parse::<Level>(),
+            Err(ParseLevelError(String::from("fatal")))
+        );
+        assert_eq!(
+            "fatal".parse::<Level>().unwrap_err().to_string(),
+            "invalid log level 'fatal', expected one of: trace, debug, info, warn, error"
+        );
+    }
+
+    #[test]
+    fn message_layout() {
+        assert_eq!(LogMessage::info("ready").format(None), " INFO ready");
+        assert_eq!(
+            LogMessage::error("lost")
+                .with_source("net")
+                .format(Some("<red>")),
+            format!("<red>ERROR{RESET} net: lost")
+        );
+
+        // 01:02:03 UTC
+        let mut message = LogMessage::warn("retrying").with_source("net");
+        message.timestamp = Some(UNIX_EPOCH + Duration::from_secs(86_400 + 3_723));
+        assert_eq!(message.format(None), "01:02:03  WARN net: retrying");
+    }
+
+    #[test]
+    fn levels_below_the_minimum_are_filtered() {
+        let level = SharedLevel::default();
+        assert!(!level.enabled(Level::Debug));
+        assert!(level.enabled(Level::Info));
+        assert!(level.enabled(Level::Error));
+
+        // clones observe changes
+        level.clone().set(Level::Error);
+        assert_eq!(level.get(), Level::Error);
+        assert!(!level.enabled(Level::Warn));
+
+        level.set(Level::Trace);
+        assert!(level.enabled(Level::Trace));
+    }
+}
@@ -1,7 +1,15 @@
 mod builder;
+mod editor;
 mod history;
+mod layout;
+mod log;
+mod multiline;
+mod pager;
+mod paste;
 mod print;
 pub(crate) mod style;
+mod transcript;
+mod vars;
 
 /// Collection of types used for auto completion of user input
 pub mod completion;
@@ -9,30 +17,46 @@ pub mod completion;
 /// Collection of types used for custom control over the EventStream
 pub mod input_hook;
 
+/// Collection of types used to display progress bars and spinners above the input line
+pub mod progress;
+
+/// Collection of types used to customize the colors of the repl
+pub mod theme;
+
 pub use builder::*;
+pub use log::*;
 pub use print::*;
+pub use style::{DefaultHighlighter, Highlighter, StyledSpan};
 
 use crate::line::{
-    completion::{Completion, Direction},
+    completion::{Completion, Diagnostic, Direction},
     history::History,
     input_hook::{AsyncCallback, HookControl, InputHook},
-    style::ansi_code::{DIM_WHITE, RED, RESET},
+    layout::{display_width, multi_line_len, right_prompt_start, rows_len, wrapped_len},
+    multiline::{join_continuations, CONTINUATION_PROMPT_LEN},
+    pager::Pager,
+    print::fit_cell,
+    progress::ProgressRows,
+    style::{ansi_code::RESET, QUOTES},
+    theme::{ColorSupport, Palette, Theme},
+    transcript::Transcript,
 };
 
 use std::{
-    collections::VecDeque,
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
     fmt::Display,
     io::{self, Write},
 };
 
-use constcat::concat;
 use crossterm::{
     cursor,
-    event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    event::{DisableBracketedPaste, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
     execute,
     style::Print,
     terminal::{
-        BeginSynchronizedUpdate, Clear, ClearType::FromCursorDown, EndSynchronizedUpdate, SetSize,
+        BeginSynchronizedUpdate, Clear, ClearType::FromCursorDown, EndSynchronizedUpdate,
+        LeaveAlternateScreen, SetSize,
     },
     QueueableCommand,
 };
@@ -40,6 +64,7 @@ use shellwords::split as shellwords_split;
 use strip_ansi::strip_ansi;
 use tokio::time::{timeout, Duration};
 use tokio_stream::StreamExt;
+use unicode_segmentation::UnicodeSegmentation;
 
 // MARK: TODOS
 // 1. Make the basic use cases as easy to set up as possible
@@ -55,6 +80,7 @@ const DEFAULT_PROMPT: &str = ">";
 const DEFAULT_PROMPT_LEN: u16 = DEFAULT_PROMPT.len() as u16 + DEFAULT_SEPARATOR.len() as u16 + 1;
 
 const NEW_LINE: &str = "\r\n";
+const BELL: &str = "\x07";
 
 /// Callback used internally by [`Repl::process_parse_err`]
 ///
@@ -71,6 +97,36 @@ impl<Ctx, W: Write, T> ParseErrHook<Ctx, W> for T where
 {
 }
 
+/// Callback used to compute the prompt on every call to [`Repl::render`]
+///
+/// `DynamicPrompt`s allow the prompt to reflect live state held within the context, eg. a connection status or
+/// the number of background jobs. The returned prompt is displayed in place of the prompt set via
+/// [`Repl::set_prompt`]. This callback can be set via [`ReplBuilder::with_dynamic_prompt`] or
+/// [`Repl::set_dynamic_prompt`].
+pub trait DynamicPrompt<Ctx>: Fn(&Ctx) -> String + Send + Sync + 'static {}
+
+impl<Ctx, T> DynamicPrompt<Ctx> for T where T: Fn(&Ctx) -> String + Send + Sync + 'static {}
+
+/// Callback used to compute the status bar displayed under the input line on every call to [`Repl::render`]
+///
+/// The status bar is a single row that can show live state held within the context, eg. the current mode, a
+/// connection status, or key hints. Text that does not fit within the width of the terminal is truncated and
+/// the status bar is not displayed when the terminal does not have enough rows left. This callback can be set
+/// via [`ReplBuilder::with_status_bar`] or [`Repl::set_status_bar`].
+pub trait StatusBar<Ctx>: Fn(&Ctx) -> String + Send + Sync + 'static {}
+
+impl<Ctx, T> StatusBar<Ctx> for T where T: Fn(&Ctx) -> String + Send + Sync + 'static {}
+
+/// Callback used to decide if the input line is incomplete when <kbd>Enter</kbd> is pressed
+///
+/// When multi-line input is enabled an incomplete input line is continued on a new row rather than submitted.
+/// Input lines that contain an unclosed quote or end with a backslash are always incomplete, returning `true`
+/// marks additional input lines as incomplete, eg. a line ending with `|`. This callback can be set via
+/// [`ReplBuilder::with_incomplete_input_predicate`] or [`Repl::set_incomplete_input_predicate`].
+pub trait IncompletePredicate: Fn(&str) -> bool + Send + Sync + 'static {}
+
+impl<T> IncompletePredicate for T where T: Fn(&str) -> bool + Send + Sync + 'static {}
+
 /// Holds all context for REPL events
 pub struct Repl<Ctx, W: Write> {
     completion: Completion,
@@ -85,13 +141,31 @@ pub struct Repl<Ctx, W: Write> {
     custom_quit: Option<Vec<String>>,
     cursor_at_start: bool,
     command_entered: bool,
+    /// <kbd>Ctrl</kbd> + <kbd>X</kbd> was pressed and the next key press completes the chord
+    ctrl_x_pending: bool,
+    /// Lines that are submitted one by one before awaiting new events, see: [`Repl::process_queued_input`]
+    queued_input: VecDeque<String>,
+    /// Minimum level of displayed [`LogMessage`]s
+    log_level: SharedLevel,
+    /// Set while the pager opened via [`Repl::page`] is displayed
+    pager: Option<Pager>,
+    progress: ProgressRows,
+    /// Set while a transcript is recorded, see: [`Repl::start_transcript`]
+    transcript: Option<Transcript>,
+    vars: HashMap<String, String>,
     input_hooks: VecDeque<InputHook<Ctx, W>>,
     parse_err_hook: ErrHook<Ctx, W>,
+    dynamic_prompt: Option<Box<dyn DynamicPrompt<Ctx>>>,
+    status_bar: Option<Box<dyn StatusBar<Ctx>>>,
 }
 
 impl<Ctx, W: Write> Drop for Repl<Ctx, W> {
     fn drop(&mut self) {
+        if self.pager.is_some() {
+            let _ = execute!(self.term, LeaveAlternateScreen);
+        }
         execute!(self.term, cursor::Show).expect("Still accepting commands");
+        let _ = execute!(self.term, DisableBracketedPaste);
         crossterm::terminal::disable_raw_mode().expect("enabled on creation");
     }
 }
@@ -103,6 +177,29 @@ enum ErrHook<Ctx, W: Write> {
     Custom(Box<dyn ParseErrHook<Ctx, W>>),
 }
 
+/// Returns if a status bar fits below the `rows_used` by the input line and hints within a terminal that is
+/// `rows` tall
+#[inline]
+fn status_bar_fits(rows_used: u16, rows: u16) -> bool {
+    rows_used < rows
+}
+
+/// Fits the given `status` onto a single row of a terminal that is `columns` wide. A `style`d status bar is
+/// padded to span the row
+fn format_status_bar(status: &str, columns: u16, style: Option<&str>) -> String {
+    // The last column is left empty so the terminal never wraps the status bar onto a new row
+    let columns = columns.saturating_sub(1);
+    let (status, width) =
+        fit_cell(&status.replace(['\n', '\r'], " "), columns, false).swap_remove(0);
+    match style {
+        Some(style) => format!(
+            "{style}{status}{}{RESET}",
+            " ".repeat(columns.saturating_sub(width) as usize)
+        ),
+        None => status,
+    }
+}
+
 #[inline]
 fn default_parse_err_hook<Ctx, W: Write>(repl: &mut Repl<Ctx, W>, err: ParseErr) -> io::Result<()> {
     repl.eprintln(err)
@@ -112,48 +209,99 @@ fn default_parse_err_hook<Ctx, W: Write>(repl: &mut Repl<Ctx, W>, err: ParseErr)
 struct LineData {
     prompt: String,
     prompt_separator: String,
+    /// Displayed right aligned on the first row of the input line, empty when not set
+    right_prompt: String,
+    /// Replaces the prompt and prompt separator of submitted lines when set
+    transient_prompt: Option<String>,
     input: String,
     comp_enabled: bool,
     style_enabled: bool,
-    err: bool,
-    len: u16,
+    hints_enabled: bool,
+    signature_enabled: bool,
+    /// Displays the hint line on the next render regardless of `hints_enabled`
+    force_hint: bool,
+    block_invalid: bool,
+    vars_enabled: bool,
+    /// Submit lines edited within an external editor once the editor exits
+    editor_submit: bool,
+    multi_line_enabled: bool,
+    incomplete_predicate: Option<Box<dyn IncompletePredicate>>,
+    /// Byte index of the end of the row the cursor is placed on, `None` when the cursor is on the last row
+    cursor_row_end: Option<usize>,
+    /// Commands of a multi-line paste that are awaiting confirmation to be submitted
+    pending_paste: Vec<String>,
+    highlighter: Option<Box<dyn Highlighter>>,
+    /// Spans of the input line classified by the [`CommandScheme`], see: [`Repl::semantic_spans`]
+    ///
+    /// [`CommandScheme`]: crate::completion::CommandScheme
+    semantic_spans: Option<Vec<StyledSpan>>,
+    palette: Palette,
+    diagnostics: Vec<Diagnostic>,
+    /// Lines of a multi-line prompt that are displayed above the row shared with the input, stripped of ansi
+    prompt_header: Vec<String>,
+    /// Formatted progress bars and spinners displayed above the prompt, see: [`Repl::add_progress`]
+    progress_rows: Vec<String>,
+    /// Display width of the last line of the prompt, prompt separator, and the space that follows
     prompt_len: u16,
 }
 
 impl LineData {
+    /// All other settings default to `false` and can be set via struct update syntax
     fn new(
         prompt: Option<String>,
         prompt_separator: Option<String>,
-        style_enabled: bool,
         completion_enabled: bool,
     ) -> Self {
         let prompt = prompt.unwrap_or_else(|| String::from(DEFAULT_PROMPT));
         let prompt_separator = prompt_separator.unwrap_or_else(|| String::from(DEFAULT_SEPARATOR));
-        LineData {
-            prompt_len: LineData::prompt_len(&prompt, &prompt_separator),
+        let mut line = LineData {
             prompt_separator,
             prompt,
-            style_enabled,
             comp_enabled: completion_enabled,
             ..Default::default()
-        }
+        };
+        line.update_prompt_len();
+        line
     }
 
-    #[inline]
     fn update_prompt_len(&mut self) {
-        self.prompt_len = Self::prompt_len(&self.prompt, &self.prompt_separator)
-    }
+        let prompt = strip_ansi(&self.prompt);
+        let mut lines = prompt
+            .split('\n')
+            .map(|line| line.trim_end_matches('\r'))
+            .collect::<Vec<_>>();
+        let last = lines.pop().expect("split always yields an item");
 
-    fn prompt_len(prompt: &str, separator: &str) -> u16 {
         // The '+ 1' is accounting for the space character located in our impl `Display` for `Self`
-        strip_ansi(prompt).chars().count() as u16 + strip_ansi(separator).chars().count() as u16 + 1
+        self.prompt_len =
+            display_width(last) + display_width(&strip_ansi(&self.prompt_separator)) + 1;
+        self.prompt_header = lines.into_iter().map(String::from).collect();
+    }
+
+    /// Only reallocates the prompt if it changed
+    fn update_prompt(&mut self, prompt: &str) {
+        if self.prompt != prompt {
+            self.prompt = String::from(prompt);
+            self.update_prompt_len();
+        }
+    }
+
+    /// Returns if the current input is refused on submission, a `forced` submission is never blocked
+    #[inline]
+    fn submission_blocked(&self, forced: bool) -> bool {
+        self.block_invalid && !self.diagnostics.is_empty() && !forced
     }
 }
 
 #[derive(Clone, Copy)]
 enum GhostTextMeta {
-    History { pos: usize },
-    Recommendation { len: usize },
+    History {
+        pos: usize,
+    },
+    Recommendation {
+        len: usize,
+        close_quote: Option<char>,
+    },
 }
 
 // MARK: TODO
@@ -187,7 +335,8 @@ impl Display for ParseErr {
 /// well as remove the queued input hook that was responsible for spawning the callback that resulted in an
 /// error.  
 ///
-/// `TryProcessInput` uses [`shellwords::split`] to parse user input into common shell tokens.
+/// `TryProcessInput` uses [`shellwords::split`] to parse user input into common shell tokens. If variable
+/// expansion is enabled, variable references are expanded prior to splitting. See: [`Repl::set_var`]
 ///
 /// [`remove_current_hook_by_error`]: Repl::remove_current_hook_by_error
 /// [`shellwords::split`]: <https://docs.rs/shell-words/latest/shell_words/fn.split.html>
@@ -219,10 +368,19 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
             render_disabled: false,
             cursor_at_start: false,
             command_entered: true,
+            ctrl_x_pending: false,
+            queued_input: VecDeque::new(),
+            log_level: SharedLevel::default(),
+            pager: None,
+            progress: ProgressRows::default(),
+            transcript: None,
+            vars: HashMap::new(),
             custom_quit,
             completion,
             input_hooks: VecDeque::new(),
             parse_err_hook: parse_err_hook.map(ErrHook::Custom).unwrap_or_default(),
+            dynamic_prompt: None,
+            status_bar: None,
         }
     }
 
@@ -357,12 +515,108 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
         self.line.style_enabled = false
     }
 
+    /// Sets the [`Theme`] used to style the repl, colors are downgraded to what the terminal supports
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.line.palette = theme.palette(ColorSupport::detect())
+    }
+
+    /// Returns if diagnostic hints are currently enabled
+    #[inline]
+    pub fn diagnostic_hints_enabled(&self) -> bool {
+        self.line.hints_enabled
+    }
+
+    /// Enables displaying the message of the first [`Diagnostic`] as a hint under the input line
+    #[inline]
+    pub fn enable_diagnostic_hints(&mut self) {
+        self.line.hints_enabled = true
+    }
+
+    /// Disables diagnostic hints
+    #[inline]
+    pub fn disable_diagnostic_hints(&mut self) {
+        self.line.hints_enabled = false
+    }
+
+    /// Returns if signature hints are currently enabled
+    #[inline]
+    pub fn signature_hints_enabled(&self) -> bool {
+        self.line.signature_enabled
+    }
+
+    /// Enables displaying the usage of the current command as a hint under the input line, the input that is
+    /// currently expected is emphasized
+    #[inline]
+    pub fn enable_signature_hints(&mut self) {
+        self.line.signature_enabled = true
+    }
+
+    /// Disables signature hints
+    #[inline]
+    pub fn disable_signature_hints(&mut self) {
+        self.line.signature_enabled = false
+    }
+
+    /// Returns if submitting input lines that contain known errors is currently blocked
+    #[inline]
+    pub fn invalid_submissions_blocked(&self) -> bool {
+        self.line.block_invalid
+    }
+
+    /// Refuses to submit input lines that contain [`Diagnostic`]s. Submission can still be forced with
+    /// <kbd>Alt</kbd> + <kbd>Enter</kbd>, unless multi-line input is enabled
+    #[inline]
+    pub fn block_invalid_submissions(&mut self) {
+        self.line.block_invalid = true
+    }
+
+    /// Allows input lines that contain [`Diagnostic`]s to be submitted
+    #[inline]
+    pub fn allow_invalid_submissions(&mut self) {
+        self.line.block_invalid = false
+    }
+
+    /// Returns all errors found within the current input line. Diagnostics are only produced when completion
+    /// is enabled
+    #[inline]
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.line.diagnostics
+    }
+
+    /// Sets the [`Highlighter`] used to style the input line
+    #[inline]
+    pub fn set_highlighter(&mut self, highlighter: impl Highlighter) {
+        self.line.highlighter = Some(Box::new(highlighter))
+    }
+
     /// Sets the currently displayed prompt
     pub fn set_prompt(&mut self, prompt: &str) {
         self.line.prompt = String::from(prompt.trim());
         self.line.update_prompt_len();
     }
 
+    /// Sets the [`DynamicPrompt`] that computes the prompt on every call to [`Self::render`], the prompt set via
+    /// [`Self::set_prompt`] is no longer displayed
+    pub fn set_dynamic_prompt(&mut self, prompt: impl DynamicPrompt<Ctx>) {
+        self.dynamic_prompt = Some(Box::new(prompt))
+    }
+
+    /// Removes the set [`DynamicPrompt`], the last computed prompt stays displayed until it is replaced
+    pub fn clear_dynamic_prompt(&mut self) {
+        self.dynamic_prompt = None
+    }
+
+    /// Sets the [`StatusBar`] that is computed and displayed under the input line on every call to
+    /// [`Self::render`]
+    pub fn set_status_bar(&mut self, status_bar: impl StatusBar<Ctx>) {
+        self.status_bar = Some(Box::new(status_bar))
+    }
+
+    /// Removes the set [`StatusBar`]
+    pub fn clear_status_bar(&mut self) {
+        self.status_bar = None
+    }
+
     /// Sets the currently displayed prompt separator  
     pub fn set_prompt_separator(&mut self, prompt_separator: &str) {
         self.line.prompt_separator = String::from(prompt_separator.trim());
@@ -376,6 +630,35 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
         self.line.update_prompt_len();
     }
 
+    /// Sets the prompt that is displayed right aligned on the first row of the input line. The right prompt is
+    /// hidden while the input line would collide with it and is never part of [`Self::input`]
+    pub fn set_right_prompt(&mut self, prompt: &str) {
+        self.line.right_prompt = String::from(prompt.trim());
+    }
+
+    /// Removes the right prompt
+    pub fn clear_right_prompt(&mut self) {
+        self.line.right_prompt.clear();
+    }
+
+    /// Returns if the transient prompt is currently enabled
+    #[inline]
+    pub fn transient_prompt_enabled(&self) -> bool {
+        self.line.transient_prompt.is_some()
+    }
+
+    /// Enables the transient prompt, once a line is submitted it is redrawn with the given minimal `prompt` in
+    /// place of the prompt and prompt separator. The full prompt is only displayed on the active line
+    pub fn enable_transient_prompt(&mut self, prompt: &str) {
+        self.line.transient_prompt = Some(String::from(prompt.trim()))
+    }
+
+    /// Disables the transient prompt
+    #[inline]
+    pub fn disable_transient_prompt(&mut self) {
+        self.line.transient_prompt = None
+    }
+
     /// Sets the currently displayed prompt to the library supplied default
     pub fn set_default_prompt(&mut self) {
         self.line.prompt = String::from(DEFAULT_PROMPT);
@@ -393,6 +676,7 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
         self.line.prompt = String::from(DEFAULT_PROMPT);
         self.line.prompt_separator = String::from(DEFAULT_SEPARATOR);
         self.line.prompt_len = DEFAULT_PROMPT_LEN;
+        self.line.prompt_header.clear();
     }
 
     /// Returns a reference to the current user input
@@ -403,11 +687,16 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
 
     /// Appends a given string slice to the end of the currently displayed input line
     pub fn append_to_line(&mut self, new: &str) -> io::Result<()> {
+        if !self.cursor_at_start {
+            self.move_to_beginning(self.cursor_len())?;
+        }
+        self.line.cursor_row_end = None;
         self.line.input.push_str(new);
-        self.line.len += new.chars().count() as u16;
-        self.move_to_end(self.line_len())?;
         self.update_completion();
-        Ok(())
+        self.term
+            .queue(Clear(FromCursorDown))?
+            .queue(Print(&self.line))?;
+        self.move_to_end(self.line_len(), false)
     }
 
     /// Gets the number of lines wrapped
@@ -416,10 +705,41 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
         line_len / self.term_size.0
     }
 
-    /// Gets the total length of the line (prompt + user input)
+    /// Gets the total length of the line (prompt + user input) in cells, including cells skipped when a wide
+    /// char is wrapped to the next row, every row of a multi-line prompt, and every continuation row of the input
     #[inline]
     fn line_len(&self) -> u16 {
-        self.line.prompt_len.saturating_add(self.line.len)
+        self.input_len(self.line.input.len())
+    }
+
+    /// Gets the length of the line in cells up until the cursor, see: [`Self::line_len`]
+    #[inline]
+    fn cursor_len(&self) -> u16 {
+        self.input_len(self.line.cursor_row_end.unwrap_or(self.line.input.len()))
+    }
+
+    /// Gets the length of the line in cells up until the given byte index of the input
+    fn input_len(&self, end: usize) -> u16 {
+        multi_line_len(
+            self.prompt_header_len()
+                .saturating_add(self.line.prompt_len),
+            &self.line.input[..end],
+            self.term_size.0,
+            CONTINUATION_PROMPT_LEN,
+        )
+    }
+
+    /// Gets the number of cells occupied by the progress rows and the rows of a multi-line prompt that are
+    /// displayed above the row shared with the input. Every line takes up at least one full row
+    fn prompt_header_len(&self) -> u16 {
+        rows_len(
+            self.line
+                .progress_rows
+                .iter()
+                .chain(&self.line.prompt_header)
+                .map(String::as_str),
+            self.term_size.0,
+        )
     }
 
     #[inline]
@@ -437,14 +757,17 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
         Ok(())
     }
 
-    fn move_to_end(&mut self, line_len: u16) -> io::Result<()> {
+    /// `from_start` signals the cursor was moved back to the beginning of the line after text was printed
+    /// beyond the input, otherwise the cursor is expected to be directly after the printed input
+    fn move_to_end(&mut self, line_len: u16, from_start: bool) -> io::Result<()> {
         let line_remaining_len = self.line_remainder(line_len);
-        if line_remaining_len == 0 {
-            self.term.queue(Print(NEW_LINE))?;
-        }
         let line_height = self.line_height(line_len);
-        if line_height != 0 && self.ghost_text.is_some() {
-            self.term.queue(cursor::MoveDown(line_height))?;
+        if from_start {
+            if line_height != 0 {
+                self.term.queue(cursor::MoveDown(line_height))?;
+            }
+        } else if line_remaining_len == 0 {
+            self.term.queue(Print(NEW_LINE))?;
         }
         self.term.queue(cursor::MoveToColumn(line_remaining_len))?;
         self.cursor_at_start = false;
@@ -474,31 +797,167 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
         if std::mem::take(&mut self.command_entered) {
             // Always assume the worst case that the user wrote into the writer without entering a new line
             // resetting the current line should make it evident the user has a bug in there code, while the
-            // library ensures to always be displaying an acceptable state
-            self.cursor_at_start = false;
+            // library ensures to always be displaying an acceptable state. The line is not yet displayed so
+            // the rows of a multi-line prompt must not be accounted for
+            self.term
+                .queue(cursor::MoveToColumn(0))?
+                .queue(Clear(FromCursorDown))?;
+            self.cursor_at_start = true;
         }
 
-        let line_len = self.line_len();
-        let line_len_sub_1 = line_len.saturating_sub(1);
-
         if !self.cursor_at_start {
-            self.move_to_beginning(line_len_sub_1)?;
+            self.move_to_beginning(self.cursor_len().saturating_sub(1))?;
             self.term.queue(Clear(FromCursorDown))?;
         }
 
+        // The dynamic prompt can only be updated once the previously displayed line has been cleared
+        if let Some(prompt) = self.dynamic_prompt.as_ref().map(|prompt| prompt(context)) {
+            self.line.update_prompt(prompt.trim());
+        }
+        self.update_progress_rows()?;
+
+        let line_len = self.line_len();
+        let line_len_sub_1 = line_len.saturating_sub(1);
+        let cursor_len = self.cursor_len();
+
+        self.line.semantic_spans = self.semantic_spans();
         self.term.queue(Print(&self.line))?;
-        self.render_ghost_text(line_len_sub_1)?;
+        let ghost_text_len = self.render_ghost_text(line_len)?;
+        let right_prompt_shown = self.render_right_prompt(line_len + ghost_text_len)?;
+        let hint_rows = self.render_hints()?;
+        let line_rows = self.line_height(line_len_sub_1 + ghost_text_len) + 1;
+        let hint_rows = hint_rows + self.render_status_bar(context, line_rows + hint_rows)?;
+
+        let from_start =
+            ghost_text_len != 0 || hint_rows != 0 || right_prompt_shown || cursor_len != line_len;
+        if from_start {
+            if hint_rows != 0 {
+                self.term.queue(cursor::MoveUp(hint_rows))?;
+            }
+            self.move_to_beginning(line_len_sub_1 + ghost_text_len)?;
+        }
 
-        self.move_to_end(line_len)?;
+        self.move_to_end(cursor_len, from_start)?;
         self.term.queue(cursor::Show)?;
 
         execute!(self.term, EndSynchronizedUpdate)
     }
 
-    fn render_ghost_text(&mut self, line_len_sub_1: u16) -> io::Result<()> {
-        if !self.line.style_enabled || self.line.input.is_empty() {
+    /// Prints the signature and diagnostic hints under the input line, returning the number of rows printed
+    fn render_hints(&mut self) -> io::Result<u16> {
+        let mut rows = 0;
+
+        if let Some(signature) = self
+            .line
+            .signature_enabled
+            .then(|| self.signature())
+            .flatten()
+        {
+            self.term.queue(Print(NEW_LINE))?.queue(Print(
+                signature.format(
+                    self.term_size.0 as usize,
+                    self.line
+                        .style_enabled
+                        .then_some(self.line.palette.hint.as_str()),
+                ),
+            ))?;
+            rows += 1;
+        }
+
+        if !self.line.pending_paste.is_empty() {
+            let confirm = format!(
+                "Run {} pasted commands? [y/N]",
+                self.line.pending_paste.len()
+            );
+            rows += self.render_hint(&confirm)?;
+        }
+
+        let force_hint = std::mem::take(&mut self.line.force_hint);
+        let Some(hint) = self
+            .line
+            .diagnostics
+            .first()
+            .filter(|_| self.line.hints_enabled || force_hint)
+            .map(|diagnostic| diagnostic.message().to_string())
+        else {
+            return Ok(rows);
+        };
+
+        Ok(rows + self.render_hint(&hint)?)
+    }
+
+    /// Prints the status bar on a new row, returning the number of rows printed. The status bar is not printed if
+    /// the `rows_used` by the input line and hints leave no room for it within the terminal
+    fn render_status_bar(&mut self, context: &Ctx, rows_used: u16) -> io::Result<u16> {
+        if !status_bar_fits(rows_used, self.term_size.1) {
+            return Ok(0);
+        }
+        let Some(status) = self
+            .status_bar
+            .as_ref()
+            .map(|status_bar| status_bar(context))
+        else {
+            return Ok(0);
+        };
+
+        let style = self
+            .line
+            .style_enabled
+            .then_some(self.line.palette.status_bar.as_str());
+        self.term
+            .queue(Print(NEW_LINE))?
+            .queue(Print(format_status_bar(&status, self.term_size.0, style)))?;
+        Ok(1)
+    }
+
+    /// Prints the given `hint` on a new row, returning the number of rows printed
+    fn render_hint(&mut self, hint: &str) -> io::Result<u16> {
+        let hint_len = wrapped_len(0, hint, self.term_size.0);
+        self.term.queue(Print(NEW_LINE))?;
+        if self.line.style_enabled {
+            self.term.queue(Print(format_args!(
+                "{}{hint}{RESET}",
+                self.line.palette.hint
+            )))?;
+        } else {
+            self.term.queue(Print(hint))?;
+        }
+        Ok(self.line_height(hint_len.saturating_sub(1)) + 1)
+    }
+
+    /// Prints the right prompt aligned to the right edge of the first row of the input, returning if it was
+    /// printed. The right prompt is hidden when the text ending at `line_end` would collide with it
+    fn render_right_prompt(&mut self, line_end: u16) -> io::Result<bool> {
+        if self.line.right_prompt.is_empty() {
+            return Ok(false);
+        }
+        let line_end = line_end - self.prompt_header_len();
+        let width = display_width(&strip_ansi(&self.line.right_prompt));
+        let Some(start) = right_prompt_start(line_end, width, self.term_size.0) else {
+            return Ok(false);
+        };
+
+        self.term.queue(cursor::MoveToColumn(start))?;
+        if self.line.style_enabled {
+            self.term.queue(Print(format_args!(
+                "{}{}{RESET}",
+                self.line.palette.right_prompt, self.line.right_prompt
+            )))?;
+        } else {
+            self.term.queue(Print(&self.line.right_prompt))?;
+        }
+        Ok(true)
+    }
+
+    /// Prints the ghost text directly after the input line, returning the number of cells the printed ghost
+    /// text occupies
+    fn render_ghost_text(&mut self, line_len: u16) -> io::Result<u16> {
+        if !self.line.style_enabled
+            || self.line.input.is_empty()
+            || self.line.cursor_row_end.is_some()
+        {
             self.ghost_text = None;
-            return Ok(());
+            return Ok(0);
         }
 
         // Render is only ran if the input state has changed, so lets try to update ghost text
@@ -506,7 +965,9 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
             .history
             .iter()
             .find_map(|(pos, prev)| {
+                // Ghost text is only ever displayed on the row of the cursor
                 prev.strip_prefix(self.input())
+                    .filter(|str| !str.contains('\n'))
                     .map(|str| (str, GhostTextMeta::History { pos }))
             })
             .or_else(|| {
@@ -517,10 +978,7 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
                     .map(|&rec| (rec, &self.completion.rec_data_from_index(0).kind))?;
 
                 let format_as_arg = self.completion.arg_format(recommendation, kind)?;
-                let mut last_token = self
-                    .input()
-                    .rsplit_once(char::is_whitespace)
-                    .map_or(self.input(), |(_, suf)| suf);
+                let (_, mut last_token) = completion::split_last_token(self.input());
 
                 if last_token.is_empty()
                     || format_as_arg
@@ -533,19 +991,43 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
                     return None;
                 }
 
-                recommendation
-                    .strip_prefix(last_token)
-                    .map(|str| (str, GhostTextMeta::Recommendation { len: str.len() }))
+                // Ghost text can only close a quote the user opened, it can not insert one
+                let close_quote = match last_token.chars().next().filter(|c| QUOTES.contains(c)) {
+                    Some(quote) if !last_token[quote.len_utf8()..].contains(quote) => {
+                        last_token = &last_token[quote.len_utf8()..];
+                        Some(quote)
+                    }
+                    Some(_) => return None,
+                    None if completion::needs_quotes(recommendation) => return None,
+                    None => None,
+                };
+
+                recommendation.strip_prefix(last_token).map(|str| {
+                    let len = str.len();
+                    (str, GhostTextMeta::Recommendation { len, close_quote })
+                })
             })
         else {
             self.ghost_text = None;
-            return Ok(());
+            return Ok(0);
+        };
+
+        let mut quote_buf = [0; 4];
+        let close_quote = match meta {
+            GhostTextMeta::Recommendation {
+                close_quote: Some(quote),
+                ..
+            } => quote.encode_utf8(&mut quote_buf),
+            _ => "",
         };
 
         self.ghost_text = Some(meta);
-        self.term
-            .queue(Print(format_args!("{DIM_WHITE}{ghost_text}{RESET}")))?;
-        self.move_to_beginning(line_len_sub_1 + ghost_text.chars().count() as u16)
+        self.term.queue(Print(format_args!(
+            "{}{ghost_text}{close_quote}{RESET}",
+            self.line.palette.ghost_text
+        )))?;
+        let ghost_text_end = wrapped_len(line_len, ghost_text, self.term_size.0);
+        Ok(wrapped_len(ghost_text_end, close_quote, self.term_size.0) - line_len)
     }
 
     /// Will skip all calls to render until [`Self::enable_render`] is called
@@ -572,25 +1054,43 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
         self.uneventful
     }
 
-    /// Pushes a char onto the input line and tries to update suggestions if completion is enabled
+    /// Pushes a char onto the input line at the cursor and tries to update suggestions if completion is enabled
     pub fn insert_char(&mut self, c: char) {
-        self.line.input.push(c);
-        self.line.len = self.line.len.saturating_add(1);
+        let Some(end) = self.line.cursor_row_end else {
+            self.line.input.push(c);
+            self.update_completion();
+            return;
+        };
+        self.line.input.insert(end, c);
+        self.line.cursor_row_end = Some(end + c.len_utf8());
+        self.reset_completion();
         self.update_completion();
     }
 
-    /// Pops a char from the input line and tries to update suggestions if completion is enabled
+    /// Pops a grapheme from the input line at the cursor and tries to update suggestions if completion is enabled
     pub fn remove_char(&mut self) -> io::Result<()> {
-        self.line.input.pop();
-        self.move_to_beginning(self.line_len())?;
+        self.move_to_beginning(self.cursor_len())?;
         self.term.queue(Clear(FromCursorDown))?;
-        self.line.len = self.line.len.saturating_sub(1);
-        self.update_completion();
+        let Some(end) = self.line.cursor_row_end else {
+            if let Some(last) = self.line.input.graphemes(true).next_back() {
+                self.line.input.truncate(self.line.input.len() - last.len());
+            }
+            self.update_completion();
+            return Ok(());
+        };
+        if let Some(last) = self.line.input[..end].graphemes(true).next_back() {
+            let i = end - last.len();
+            self.line.input.replace_range(i..end, "");
+            self.line.cursor_row_end = Some(i);
+            self.reset_completion();
+            self.update_completion();
+        }
         Ok(())
     }
 
     /// Writes the current line to the terminal and returns the user input of the line
     pub fn new_line(&mut self) -> io::Result<String> {
+        self.clear_progress_rows()?;
         self.term
             .queue(Clear(FromCursorDown))?
             .queue(Print(NEW_LINE))?;
@@ -601,12 +1101,15 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
     /// Appends "^C" (color coded if style is enabled) to the current line, writes it to the terminal,
     /// and returns the user input of the line
     pub fn ctrl_c_line(&mut self) -> io::Result<String> {
+        self.move_cursor_to_input_end()?;
+        self.clear_progress_rows()?;
+        if self.line.style_enabled {
+            self.term
+                .queue(Print(format_args!("{}^C{RESET}", self.line.palette.ctrl_c)))?;
+        } else {
+            self.term.queue(Print("^C"))?;
+        }
         self.term
-            .queue(Print(if self.line.style_enabled {
-                concat!(RED, "^C", RESET)
-            } else {
-                "^C"
-            }))?
             .queue(Clear(FromCursorDown))?
             .queue(Print(NEW_LINE))?;
         self.cursor_at_start = true;
@@ -615,7 +1118,7 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
 
     /// Clears the current line and returns the user input of the line
     pub fn clear_line(&mut self) -> io::Result<String> {
-        self.move_to_beginning(self.line_len())?;
+        self.move_to_beginning(self.cursor_len())?;
         self.term.queue(Clear(FromCursorDown))?;
         Ok(self.reset_line_state())
     }
@@ -625,9 +1128,9 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
     fn reset_line_state(&mut self) -> String {
         self.reset_completion();
         self.history.reset_idx();
-        self.line.len = 0;
-        self.line.err = false;
+        self.line.diagnostics.clear();
         self.ghost_text = None;
+        self.line.cursor_row_end = None;
         std::mem::take(&mut self.line.input)
     }
 
@@ -643,9 +1146,11 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
     /// For internal use when we **know** that we want to keep the same completion state, returning you
     /// an owned `String` of what was replaced.
     fn change_line_raw(&mut self, mut line: String) -> io::Result<String> {
-        self.move_to_beginning(self.line_len())?;
+        if !self.cursor_at_start {
+            self.move_to_beginning(self.cursor_len())?;
+        }
         self.term.queue(Clear(FromCursorDown))?;
-        self.line.len = line.chars().count() as u16;
+        self.line.cursor_row_end = None;
         std::mem::swap(&mut self.line.input, &mut line);
         Ok(line)
     }
@@ -663,13 +1168,36 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
         Ok(())
     }
 
-    fn enter_command(&mut self) -> io::Result<&str> {
+    /// Returns the entered line with its line continuations joined and its variables expanded if variable
+    /// expansion is enabled
+    fn enter_command(&mut self) -> io::Result<Cow<'_, str>> {
         self.term.queue(cursor::Hide)?;
+        self.move_cursor_to_input_end()?;
+        self.clear_progress_rows()?;
+        if let Some(prompt) = self.line.transient_prompt.as_deref() {
+            let line = self.line.transient(prompt);
+            if !self.cursor_at_start {
+                self.move_to_beginning(self.line_len())?;
+            }
+            self.term.queue(Clear(FromCursorDown))?.queue(Print(line))?;
+        }
         let cmd = self.new_line()?;
+        self.record_input(&cmd)?;
         self.add_to_history(&cmd);
         self.command_entered = true;
 
-        Ok(self.history.last_entry().expect("just pushed into history"))
+        let entry = self.history.last_entry().expect("just pushed into history");
+        Ok(match join_continuations(entry) {
+            Cow::Borrowed(line) => self.expand_vars(line),
+            Cow::Owned(line) => Cow::Owned(self.expand_vars(&line).into_owned()),
+        })
+    }
+
+    /// Signals the current line was not submitted by ringing the terminal bell and displaying the reason
+    fn reject_submission(&mut self) -> io::Result<()> {
+        self.term.queue(Print(BELL))?;
+        self.line.force_hint = true;
+        Ok(())
     }
 
     fn append_ghost_text(&mut self) -> io::Result<()> {
@@ -687,10 +1215,13 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
                         .to_string(),
                 )?;
             }
-            GhostTextMeta::Recommendation { len } => {
+            GhostTextMeta::Recommendation { len, close_quote } => {
                 let rec_len = self.completion.recommendations[0].len();
                 let ghost_text = &self.completion.recommendations[0][rec_len - len..];
                 self.append_to_line(ghost_text)?;
+                if let Some(quote) = close_quote {
+                    self.append_to_line(quote.encode_utf8(&mut [0; 4]))?;
+                }
             }
         }
 
@@ -713,7 +1244,8 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
     }
 
     /// The main control flow for awaited events from a [`crossterm::event::EventStream`]. Works well as its
-    /// own branch in a [`tokio::select!`].
+    /// own branch in a [`tokio::select!`]. Lines queued by a confirmed multi-line paste must be submitted via
+    /// [`Self::process_queued_input`] before awaiting the next event.
     ///
     /// # Example
     ///
@@ -783,6 +1315,25 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
 
         execute!(self.term, BeginSynchronizedUpdate)?;
 
+        let ctrl_x_pending = match event {
+            Event::Key(KeyEvent {
+                kind: KeyEventKind::Press,
+                ..
+            }) => std::mem::take(&mut self.ctrl_x_pending),
+            _ => false,
+        };
+
+        if !self.line.pending_paste.is_empty() {
+            if let Event::Key(KeyEvent {
+                code,
+                kind: KeyEventKind::Press,
+                ..
+            }) = event
+            {
+                return self.confirm_paste(matches!(code, KeyCode::Char('y' | 'Y')));
+            }
+        }
+
         match event {
             Event::Key(KeyEvent {
                 code: KeyCode::Char('c'),
@@ -801,6 +1352,21 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
                 modifiers: KeyModifiers::CONTROL,
                 ..
             }) => return self.process_close_signal(),
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('x'),
+                kind: KeyEventKind::Press,
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            }) => {
+                self.ctrl_x_pending = true;
+                self.set_uneventful();
+            }
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('e'),
+                kind: KeyEventKind::Press,
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            }) if ctrl_x_pending => return self.edit_in_editor(self.line.editor_submit),
             Event::Key(KeyEvent {
                 code: KeyCode::Tab,
                 kind: KeyEventKind::Press,
@@ -830,27 +1396,51 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
                 code: KeyCode::Up,
                 kind: KeyEventKind::Press,
                 ..
-            }) => self.history_back()?,
+            }) => {
+                if !self.cursor_row_up()? {
+                    self.history_back()?
+                }
+            }
             Event::Key(KeyEvent {
                 code: KeyCode::Down,
                 kind: KeyEventKind::Press,
                 ..
-            }) => self.history_forward()?,
+            }) => {
+                if !self.cursor_row_down()? {
+                    self.history_forward()?
+                }
+            }
             Event::Key(KeyEvent {
                 code: KeyCode::Enter,
                 kind: KeyEventKind::Press,
+                modifiers,
                 ..
             }) => {
-                if !self.input().trim().is_empty() {
+                let alt = modifiers.contains(KeyModifiers::ALT);
+                if self.line.multi_line_enabled && (alt || self.input_incomplete()) {
+                    self.insert_newline()?;
+                } else if !self.input().trim().is_empty() {
+                    // Alt + Enter only reaches here when multi-line input is disabled, it then forces the
+                    // submission of a blocked line
+                    if self.line.submission_blocked(alt) {
+                        self.reject_submission()?;
+                        return Ok(EventLoop::Continue);
+                    }
                     return Ok(EventLoop::TryProcessInput(
-                        shellwords_split(self.enter_command()?)
+                        shellwords_split(&self.enter_command()?)
                             .map_err(|_| ParseErr::MismatchedQuotes),
                     ));
+                } else {
+                    self.new_line()?;
                 }
-                self.new_line()?;
             }
-            Event::Resize(x, y) if self.term_size != (x, y) => self.term_size = (x, y),
-            Event::Paste(new) if !self.render_disabled => self.append_to_line(&new)?,
+            Event::Resize(x, y) if self.term_size != (x, y) => {
+                self.term_size = (x, y);
+                if self.pager.is_some() {
+                    self.draw_pager()?;
+                }
+            }
+            Event::Paste(new) if !self.render_disabled => self.paste(&new)?,
             _ => self.set_uneventful(),
         }
         if self.uneventful {
@@ -859,3 +1449,87 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
         Ok(EventLoop::Continue)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::line::completion::DiagnosticKind;
+
+    fn line_with_error(block_invalid: bool) -> LineData {
+        let input = String::from("ech");
+        LineData {
+            diagnostics: vec![Diagnostic::new(
+                DiagnosticKind::UnknownCommand,
+                0..input.len(),
+                &input,
+            )],
+            input,
+            block_invalid,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn submission_blocking() {
+        assert!(line_with_error(true).submission_blocked(false));
+        assert!(!line_with_error(true).submission_blocked(true));
+        assert!(!line_with_error(false).submission_blocked(false));
+
+        let valid = LineData {
+            input: String::from("echo"),
+            block_invalid: true,
+            ..Default::default()
+        };
+        assert!(!valid.submission_blocked(false));
+    }
+
+    #[test]
+    fn dynamic_prompt_updates_prompt_len() {
+        let mut line = LineData::new(None, None, false);
+        line.update_prompt("\x1b[32mjobs: 2\x1b[0m");
+        // "jobs: 2" + ">" + ' '
+        assert_eq!(line.prompt_len, 9);
+
+        let prompt = line.prompt.as_ptr();
+        line.update_prompt("\x1b[32mjobs: 2\x1b[0m");
+        assert_eq!(line.prompt.as_ptr(), prompt);
+
+        line.update_prompt("jobs: 12");
+        assert_eq!(line.prompt_len, 10);
+    }
+
+    #[test]
+    fn multi_line_prompt_header() {
+        let line = LineData::new(
+            Some(String::from("\x1b[2mcluster: prod\x1b[0m\r\n\nnodes 3")),
+            None,
+            false,
+        );
+        assert_eq!(line.prompt_header, ["cluster: prod", ""]);
+        // "nodes 3" + ">" + ' '
+        assert_eq!(line.prompt_len, 9);
+        // "cluster: prod" wraps onto two rows, the empty line takes up one
+        assert_eq!(
+            rows_len(line.prompt_header.iter().map(String::as_str), 10) + line.prompt_len,
+            39
+        );
+    }
+
+    #[test]
+    fn status_bar_is_suppressed_on_short_terminals() {
+        // input line and hint rows
+        assert!(status_bar_fits(2, 3));
+        assert!(!status_bar_fits(3, 3));
+        assert!(!status_bar_fits(4, 3));
+    }
+
+    #[test]
+    fn status_bar_fits_a_single_row() {
+        assert_eq!(format_status_bar("jobs: 2\nok", 20, None), "jobs: 2 ok");
+        assert_eq!(format_status_bar("jobs: 2", 6, None), "jobs…");
+        assert_eq!(
+            format_status_bar("jobs: 2", 10, Some("<bar>")),
+            format!("<bar>jobs: 2  {RESET}")
+        );
+    }
+}
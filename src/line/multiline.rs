@@ -0,0 +1,201 @@
+use crate::line::{style::QUOTES, IncompletePredicate, Repl};
+
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use crossterm::{
+    style::Print,
+    terminal::{Clear, ClearType::FromCursorDown},
+    QueueableCommand,
+};
+
+/// Displayed at the start of every continuation row of a multi-line input line
+pub(super) const CONTINUATION_PROMPT: &str = "...";
+
+// The '+ 1' is accounting for the space character that follows the continuation prompt
+pub(super) const CONTINUATION_PROMPT_LEN: u16 = CONTINUATION_PROMPT.len() as u16 + 1;
+
+/// Returns if the given `line` contains an unclosed quote or ends with a backslash that is not within single
+/// quotes
+fn ends_open(line: &str) -> bool {
+    let mut open_quote = None;
+    let mut escaped = false;
+
+    for ch in line.chars() {
+        if std::mem::take(&mut escaped) {
+            continue;
+        }
+        match open_quote {
+            Some(quote) if ch == quote => open_quote = None,
+            Some('\'') => (),
+            _ if ch == '\\' => escaped = true,
+            Some(_) => (),
+            None if QUOTES.contains(&ch) => open_quote = Some(ch),
+            None => (),
+        }
+    }
+
+    open_quote.is_some() || escaped
+}
+
+/// Removes every line continuation, a backslash directly followed by a new line, from the given `line`. Line
+/// continuations within single quotes are left untouched
+pub(super) fn join_continuations(line: &str) -> Cow<'_, str> {
+    if !line.contains("\\\n") {
+        return Cow::Borrowed(line);
+    }
+
+    let mut joined = String::with_capacity(line.len());
+    let mut open_quote = None;
+    let mut escaped = false;
+
+    for ch in line.chars() {
+        if std::mem::take(&mut escaped) {
+            if ch != '\n' {
+                joined.push('\\');
+                joined.push(ch);
+            }
+            continue;
+        }
+        match open_quote {
+            Some(quote) if ch == quote => open_quote = None,
+            Some('\'') => (),
+            _ if ch == '\\' => {
+                escaped = true;
+                continue;
+            }
+            Some(_) => (),
+            None if QUOTES.contains(&ch) => open_quote = Some(ch),
+            None => (),
+        }
+        joined.push(ch);
+    }
+
+    if escaped {
+        joined.push('\\');
+    }
+    Cow::Owned(joined)
+}
+
+impl<Ctx, W: Write> Repl<Ctx, W> {
+    /// Returns if multi-line input is currently enabled
+    #[inline]
+    pub fn multi_line_input_enabled(&self) -> bool {
+        self.line.multi_line_enabled
+    }
+
+    /// Enables multi-line input, pressing <kbd>Enter</kbd> on an incomplete input line continues the input on a
+    /// new row rather than submitting it. A new row can always be inserted with <kbd>Alt</kbd> + <kbd>Enter</kbd>,
+    /// so it no longer forces the submission of a line refused by [`Self::block_invalid_submissions`].
+    /// See: [`IncompletePredicate`]
+    #[inline]
+    pub fn enable_multi_line_input(&mut self) {
+        self.line.multi_line_enabled = true
+    }
+
+    /// Disables multi-line input
+    #[inline]
+    pub fn disable_multi_line_input(&mut self) {
+        self.line.multi_line_enabled = false
+    }
+
+    /// Sets the [`IncompletePredicate`] used to mark additional input lines as incomplete, enables multi-line
+    /// input
+    pub fn set_incomplete_input_predicate(&mut self, predicate: impl IncompletePredicate) {
+        self.line.incomplete_predicate = Some(Box::new(predicate));
+        self.line.multi_line_enabled = true;
+    }
+
+    /// Returns if the current input line can not be submitted yet
+    pub(super) fn input_incomplete(&self) -> bool {
+        ends_open(&self.line.input)
+            || self
+                .line
+                .incomplete_predicate
+                .as_ref()
+                .is_some_and(|predicate| predicate(&self.line.input))
+    }
+
+    /// Inserts a new row into the input line at the cursor
+    pub(super) fn insert_newline(&mut self) -> io::Result<()> {
+        self.move_to_beginning(self.cursor_len())?;
+        self.term.queue(Clear(FromCursorDown))?;
+        self.insert_char('\n');
+        Ok(())
+    }
+
+    /// Moves the cursor to the end of the previous row of the input line, returning `false` if the cursor is
+    /// already on the first row
+    pub(super) fn cursor_row_up(&mut self) -> io::Result<bool> {
+        let end = self.line.cursor_row_end.unwrap_or(self.line.input.len());
+        let Some(prev_end) = self.line.input[..end].rfind('\n') else {
+            return Ok(false);
+        };
+        self.move_to_beginning(self.cursor_len())?;
+        self.term.queue(Clear(FromCursorDown))?;
+        self.line.cursor_row_end = Some(prev_end);
+        Ok(true)
+    }
+
+    /// Moves the cursor to the end of the next row of the input line, returning `false` if the cursor is already
+    /// on the last row
+    pub(super) fn cursor_row_down(&mut self) -> io::Result<bool> {
+        let Some(end) = self.line.cursor_row_end else {
+            return Ok(false);
+        };
+        self.move_to_beginning(self.cursor_len())?;
+        self.term.queue(Clear(FromCursorDown))?;
+        self.line.cursor_row_end = self.line.input[end + 1..]
+            .find('\n')
+            .map(|next_end| end + 1 + next_end);
+        Ok(true)
+    }
+
+    /// Redraws the input line so the cursor is placed at the end of the last row, required before writing
+    /// anything after the input line
+    pub(super) fn move_cursor_to_input_end(&mut self) -> io::Result<()> {
+        if self.line.cursor_row_end.is_none() || self.cursor_at_start {
+            self.line.cursor_row_end = None;
+            return Ok(());
+        }
+        self.move_to_beginning(self.cursor_len())?;
+        self.line.cursor_row_end = None;
+        self.term
+            .queue(Clear(FromCursorDown))?
+            .queue(Print(&self.line))?;
+        self.move_to_end(self.line_len(), false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_line_ends() {
+        assert!(!ends_open("echo hi"));
+        assert!(ends_open("echo 'hi"));
+        assert!(ends_open("echo \"hi"));
+        assert!(ends_open("echo hi \\"));
+        assert!(!ends_open("echo hi \\\\"));
+        // escaped quotes do not open a quote
+        assert!(!ends_open("echo \\'hi"));
+        assert!(ends_open("echo \"a \\\" b"));
+        // backslashes within single quotes are literal
+        assert!(!ends_open("echo 'a \\' b"));
+        assert!(ends_open("echo 'a b \\"));
+    }
+
+    #[test]
+    fn continuations_are_joined() {
+        assert!(matches!(join_continuations("echo hi"), Cow::Borrowed(_)));
+        assert_eq!(join_continuations("echo \\\nhi \\\nthere"), "echo hi there");
+        assert_eq!(join_continuations("echo \"a \\\nb\""), "echo \"a b\"");
+        // new lines that do not follow a backslash are kept
+        assert_eq!(join_continuations("echo a\\\nb\nc"), "echo ab\nc");
+        assert_eq!(join_continuations("echo 'a \\\nb'"), "echo 'a \\\nb'");
+        assert_eq!(join_continuations("echo \\\\\\\nhi"), "echo \\\\hi");
+    }
+}
@@ -0,0 +1,462 @@
+use crate::line::{
+    input_hook::{HookStates, HookedEvent, InputHook},
+    layout::{ansi_segments, display_width},
+    print::fit_cell,
+    style::ansi_code::RESET,
+    Repl,
+};
+
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use crossterm::{
+    cursor,
+    event::{Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    style::Print,
+    terminal::{
+        BeginSynchronizedUpdate, Clear, ClearType, EndSynchronizedUpdate, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+    QueueableCommand,
+};
+use strip_ansi::strip_ansi;
+
+const REVERSE: &str = "\x1b[7m";
+const REVERSE_OFF: &str = "\x1b[27m";
+
+/// State of the pager opened via [`Repl::page`]
+pub(super) struct Pager {
+    lines: Vec<String>,
+    /// Index of the first displayed line
+    top: usize,
+    /// Pattern that is highlighted and jumped between with `n` / `N`
+    pattern: Option<String>,
+    /// Index of the line the last search jumped to
+    current_match: Option<usize>,
+    /// Pattern that is currently being typed after `/`
+    search_input: Option<String>,
+    /// Displayed in the status row until the next key press
+    message: Option<&'static str>,
+    /// Background messages received while the pager is open, written out once the pager is closed
+    pub(super) buffered: Vec<u8>,
+}
+
+/// Returns the number of rows the given `line` takes up when displayed
+fn line_height(line: &str, columns: u16) -> usize {
+    (display_width(&strip_ansi(line)) as usize)
+        .div_ceil(columns.max(1) as usize)
+        .max(1)
+}
+
+/// Splits the given `line` into the rows it is displayed on, keeping only the first `rows` rows so a line that is
+/// taller than the screen never overwrites the status row
+fn clip_rows(line: &str, columns: u16, rows: usize) -> Vec<String> {
+    let mut clipped = fit_cell(line, columns.max(1), true);
+    clipped.truncate(rows);
+    clipped.into_iter().map(|(row, _)| row).collect()
+}
+
+/// Wraps every occurrence of `pattern` within the displayed text of `line` in reverse video. Escape sequences
+/// within `line` are passed through untouched, see: [`ansi_segments`]
+fn highlight<'a>(line: &'a str, pattern: &str) -> Cow<'a, str> {
+    if pattern.is_empty() {
+        return Cow::Borrowed(line);
+    }
+
+    let mut visible = String::with_capacity(line.len());
+    // Byte index within `line` of every byte in `visible`
+    let mut raw_i = Vec::with_capacity(line.len());
+
+    for (range, _) in ansi_segments(line)
+        .into_iter()
+        .filter(|(_, escape)| !escape)
+    {
+        visible.push_str(&line[range.clone()]);
+        raw_i.extend(range);
+    }
+
+    let mut highlighted = String::new();
+    let mut copied = 0;
+    for (start, found) in visible.match_indices(pattern) {
+        let (start, end) = (raw_i[start], raw_i[start + found.len() - 1] + 1);
+        highlighted.push_str(&line[copied..start]);
+        highlighted.push_str(REVERSE);
+        highlighted.push_str(&line[start..end]);
+        highlighted.push_str(REVERSE_OFF);
+        copied = end;
+    }
+
+    if copied == 0 {
+        return Cow::Borrowed(line);
+    }
+    highlighted.push_str(&line[copied..]);
+    Cow::Owned(highlighted)
+}
+
+impl Pager {
+    fn new(lines: Vec<String>) -> Self {
+        Pager {
+            lines,
+            top: 0,
+            pattern: None,
+            current_match: None,
+            search_input: None,
+            message: None,
+            buffered: Vec::new(),
+        }
+    }
+
+    /// Returns the index one past the last line that fits within `rows` when starting at `top`
+    fn visible_end(&self, top: usize, columns: u16, rows: usize) -> usize {
+        let mut used = 0;
+        let mut end = top;
+        while let Some(line) = self.lines.get(end) {
+            used += line_height(line, columns);
+            if used > rows && end != top {
+                break;
+            }
+            end += 1;
+        }
+        end
+    }
+
+    /// Returns the first line displayed when the last line is at the bottom of the screen
+    fn max_top(&self, columns: u16, rows: usize) -> usize {
+        self.top_above(self.lines.len(), columns, rows)
+    }
+
+    /// Returns the first line of the page that ends right before the line at `end`
+    fn top_above(&self, end: usize, columns: u16, rows: usize) -> usize {
+        let mut used = 0;
+        let mut top = end;
+        while top > 0 {
+            used += line_height(&self.lines[top - 1], columns);
+            if used > rows && top != end {
+                break;
+            }
+            top -= 1;
+        }
+        top
+    }
+
+    fn scroll_to(&mut self, top: usize, columns: u16, rows: usize) {
+        self.top = top.min(self.max_top(columns, rows));
+    }
+
+    /// Jumps to the next line that contains the search pattern, searching backwards if not `forward`
+    fn find(&mut self, forward: bool, columns: u16, rows: usize) {
+        let Some(ref pattern) = self.pattern else {
+            return;
+        };
+        let from = self.current_match.filter(|&i| i >= self.top);
+        let matches = |i: &usize| strip_ansi(&self.lines[*i]).contains(pattern.as_str());
+
+        let found = if forward {
+            (from.map_or(self.top, |i| i + 1)..self.lines.len()).find(matches)
+        } else {
+            (0..from.unwrap_or(self.top)).rev().find(matches)
+        };
+
+        match found {
+            Some(i) => {
+                self.current_match = Some(i);
+                self.scroll_to(i, columns, rows);
+            }
+            None => self.message = Some("Pattern not found"),
+        }
+    }
+
+    /// Returns the text displayed within the status row
+    fn status(&self, columns: u16, rows: usize) -> String {
+        if let Some(ref input) = self.search_input {
+            return format!("/{input}");
+        }
+        if let Some(message) = self.message {
+            return format!("{REVERSE}{message}{RESET}");
+        }
+        let end = self.visible_end(self.top, columns, rows);
+        if end == self.lines.len() {
+            return format!("{REVERSE}(END){RESET}");
+        }
+        format!(
+            "{REVERSE}lines {}-{end}/{} {}%{RESET}",
+            self.top + 1,
+            self.lines.len(),
+            end * 100 / self.lines.len()
+        )
+    }
+}
+
+impl<Ctx, W: Write> Repl<Ctx, W> {
+    /// Displays the given `text` one screen at a time within the terminal's alternate screen, similar to
+    /// `less -R`. Escape sequences within `text` are passed through to the terminal. Text that fits on a
+    /// single screen is printed as if it were passed to [`Repl::print_lines`].
+    ///
+    /// The pager runs as an [`InputHook`] so it is opened once the repl is rendered. While the pager is open
+    /// messages printed via [`Repl::println`], [`Repl::eprintln`], and [`Repl::print_lines`] are buffered and
+    /// printed once the pager is closed.
+    ///
+    /// | Key                                                 | Action                           |
+    /// | --------------------------------------------------- | -------------------------------- |
+    /// | <kbd>Space</kbd> / <kbd>f</kbd>                     | Next page                        |
+    /// | <kbd>b</kbd>                                        | Previous page                    |
+    /// | <kbd>j</kbd> / <kbd>↓</kbd> / <kbd>Enter</kbd>      | Next line                        |
+    /// | <kbd>k</kbd> / <kbd>↑</kbd>                         | Previous line                    |
+    /// | <kbd>g</kbd> / <kbd>G</kbd>                         | First / last page                |
+    /// | <kbd>/</kbd>                                        | Search, matches are highlighted  |
+    /// | <kbd>n</kbd> / <kbd>N</kbd>                         | Next / previous match            |
+    /// | <kbd>q</kbd> / <kbd>Esc</kbd>                       | Quit                             |
+    ///
+    /// [`InputHook`]: crate::line::input_hook::InputHook
+    pub fn page<S: AsRef<str>>(&mut self, text: S) -> io::Result<()>
+    where
+        Ctx: 'static,
+        W: 'static,
+    {
+        let (columns, rows) = self.pager_size();
+        let lines = text
+            .as_ref()
+            .lines()
+            .map(|line| line.replace('\t', "    "))
+            .collect::<Vec<_>>();
+
+        if lines
+            .iter()
+            .map(|line| line_height(line, columns))
+            .sum::<usize>()
+            <= rows
+        {
+            return self.print_lines(text);
+        }
+        self.record_output(text.as_ref())?;
+
+        self.register_input_hook(InputHook::with_new_uid(
+            HookStates::new(
+                move |repl: &mut Repl<Ctx, W>, _: &mut Ctx| repl.open_pager(lines),
+                |repl: &mut Repl<Ctx, W>, _: &mut Ctx| repl.close_pager(),
+            ),
+            |repl: &mut Repl<Ctx, W>, _: &mut Ctx, event| repl.pager_event(event),
+        ));
+        Ok(())
+    }
+
+    /// Returns the columns and rows available to the pager's text
+    fn pager_size(&self) -> (u16, usize) {
+        let (columns, rows) = self.term_size;
+        (columns, (rows as usize).saturating_sub(1).max(1))
+    }
+
+    fn open_pager(&mut self, lines: Vec<String>) -> io::Result<()> {
+        self.pager = Some(Pager::new(lines));
+        self.disable_render();
+
+        // Inputs must not be discarded while paging, `command_entered` is restored once the pager is closed
+        self.command_entered = false;
+
+        self.term.queue(EnterAlternateScreen)?;
+        self.draw_pager()
+    }
+
+    fn close_pager(&mut self) -> io::Result<()> {
+        let pager = self.pager.take().expect("only closed by the pager's hook");
+        self.enable_render();
+        self.command_entered = true;
+
+        self.term.queue(LeaveAlternateScreen)?;
+        if !pager.buffered.is_empty() {
+            self.prep_for_background_msg()?;
+            self.term.write_all(&pager.buffered)?;
+        }
+        self.term.flush()
+    }
+
+    /// Redraws the pager's current page and status row
+    pub(super) fn draw_pager(&mut self) -> io::Result<()> {
+        let (columns, rows) = self.pager_size();
+        let pager = self
+            .pager
+            .as_ref()
+            .expect("only drawn while the pager is open");
+
+        self.term
+            .queue(BeginSynchronizedUpdate)?
+            .queue(cursor::Hide)?
+            .queue(cursor::MoveTo(0, 0))?
+            .queue(Clear(ClearType::All))?;
+
+        let mut row = 0;
+        for line in &pager.lines[pager.top..pager.visible_end(pager.top, columns, rows)] {
+            let line = match pager.pattern {
+                Some(ref pattern) => highlight(line, pattern),
+                None => Cow::Borrowed(line.as_str()),
+            };
+            // Only the first displayed line can be taller than the screen
+            if line_height(&line, columns) > rows - row {
+                for (i, clipped) in clip_rows(&line, columns, rows - row).iter().enumerate() {
+                    self.term
+                        .queue(cursor::MoveTo(0, (row + i) as u16))?
+                        .queue(Print(clipped))?
+                        .queue(Print(RESET))?;
+                }
+                break;
+            }
+            self.term
+                .queue(cursor::MoveTo(0, row as u16))?
+                .queue(Print(&line))?
+                .queue(Print(RESET))?;
+            row += line_height(&line, columns);
+        }
+
+        self.term
+            .queue(cursor::MoveTo(0, rows as u16))?
+            .queue(Print(pager.status(columns, rows)))?;
+        if pager.search_input.is_some() {
+            self.term.queue(cursor::Show)?;
+        }
+        execute!(self.term, EndSynchronizedUpdate)
+    }
+
+    fn pager_event(&mut self, event: Event) -> io::Result<HookedEvent<Ctx, W>> {
+        let Event::Key(KeyEvent {
+            code, modifiers, ..
+        }) = event
+        else {
+            return HookedEvent::continue_hook();
+        };
+
+        let (columns, rows) = self.pager_size();
+        let pager = self
+            .pager
+            .as_mut()
+            .expect("hook is only active while the pager is open");
+        pager.message = None;
+
+        if let Some(ref mut input) = pager.search_input {
+            match code {
+                KeyCode::Char(ch) => input.push(ch),
+                KeyCode::Backspace => {
+                    if input.pop().is_none() {
+                        pager.search_input = None;
+                    }
+                }
+                KeyCode::Enter => {
+                    let pattern = pager.search_input.take().expect("outer if");
+                    if !pattern.is_empty() {
+                        pager.pattern = Some(pattern);
+                        pager.current_match = None;
+                        pager.find(true, columns, rows);
+                    }
+                }
+                KeyCode::Esc => pager.search_input = None,
+                _ => return HookedEvent::continue_hook(),
+            }
+            self.draw_pager()?;
+            return HookedEvent::continue_hook();
+        }
+
+        match code {
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                return HookedEvent::release_hook()
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return HookedEvent::release_hook(),
+            KeyCode::Char(' ' | 'f') | KeyCode::PageDown => {
+                let end = pager.visible_end(pager.top, columns, rows);
+                pager.scroll_to(end, columns, rows)
+            }
+            KeyCode::Char('b') | KeyCode::PageUp => {
+                pager.top = pager.top_above(pager.top, columns, rows)
+            }
+            KeyCode::Char('j') | KeyCode::Down | KeyCode::Enter => {
+                pager.scroll_to(pager.top + 1, columns, rows)
+            }
+            KeyCode::Char('k') | KeyCode::Up => pager.top = pager.top.saturating_sub(1),
+            KeyCode::Char('g') | KeyCode::Home => pager.top = 0,
+            KeyCode::Char('G') | KeyCode::End => pager.top = pager.max_top(columns, rows),
+            KeyCode::Char('/') => pager.search_input = Some(String::new()),
+            KeyCode::Char('n') => pager.find(true, columns, rows),
+            KeyCode::Char('N') => pager.find(false, columns, rows),
+            _ => return HookedEvent::continue_hook(),
+        }
+        self.draw_pager()?;
+        HookedEvent::continue_hook()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pager(lines: &[&str]) -> Pager {
+        Pager::new(lines.iter().copied().map(String::from).collect())
+    }
+
+    #[test]
+    fn highlight_passes_escapes_through() {
+        assert_eq!(highlight("abc", "x"), "abc");
+        assert_eq!(
+            highlight("ab\x1b[31mcd\x1b[0m", "bc"),
+            format!("a{REVERSE}b\x1b[31mc{REVERSE_OFF}d\x1b[0m")
+        );
+        assert_eq!(
+            highlight("a-a", "a"),
+            format!("{REVERSE}a{REVERSE_OFF}-{REVERSE}a{REVERSE_OFF}")
+        );
+    }
+
+    #[test]
+    fn paging_wrapped_lines() {
+        // columns: 10, every `long` line takes up 2 rows
+        let long = "0123456789abc";
+        let mut pager = pager(&["a", long, "b", long, "c", "d"]);
+        assert_eq!(pager.visible_end(0, 10, 4), 3);
+        assert_eq!(pager.visible_end(1, 10, 4), 3);
+        assert_eq!(pager.max_top(10, 4), 3);
+        assert_eq!(pager.top_above(3, 10, 4), 0);
+        assert_eq!(pager.top_above(4, 10, 4), 2);
+
+        pager.scroll_to(pager.visible_end(0, 10, 4), 10, 4);
+        assert_eq!(pager.top, 3);
+        assert_eq!(pager.status(10, 4), format!("{REVERSE}(END){RESET}"));
+        pager.top = 0;
+        assert_eq!(
+            pager.status(10, 4),
+            format!("{REVERSE}lines 1-3/6 50%{RESET}")
+        );
+    }
+
+    #[test]
+    fn lines_taller_than_the_screen_are_clipped() {
+        let line = format!("\x1b[31m{}", "x".repeat(35));
+        let clipped = clip_rows(&line, 10, 2);
+        assert_eq!(clipped.len(), 2);
+        assert!(clipped
+            .iter()
+            .all(|row| display_width(&strip_ansi(row)) == 10));
+        // a lone line is still displayed on its own page
+        assert_eq!(pager(&[&line]).visible_end(0, 10, 2), 1);
+    }
+
+    #[test]
+    fn find_next_and_previous_match() {
+        let mut pager = pager(&["match", "a", "b", "\x1b[1mmatch\x1b[0m", "c", "match", "d"]);
+        pager.pattern = Some(String::from("match"));
+
+        pager.find(true, 10, 2);
+        assert_eq!((pager.current_match, pager.top), (Some(0), 0));
+        pager.find(true, 10, 2);
+        assert_eq!((pager.current_match, pager.top), (Some(3), 3));
+        pager.find(true, 10, 2);
+        assert_eq!((pager.current_match, pager.top), (Some(5), 5));
+        pager.find(true, 10, 2);
+        assert_eq!(pager.message, Some("Pattern not found"));
+        assert_eq!(pager.current_match, Some(5));
+
+        pager.find(false, 10, 2);
+        assert_eq!((pager.current_match, pager.top), (Some(3), 3));
+        pager.find(false, 10, 2);
+        assert_eq!((pager.current_match, pager.top), (Some(0), 0));
+    }
+}
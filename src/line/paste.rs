@@ -0,0 +1,144 @@
+use crate::line::{EventLoop, ParseErr, Repl};
+
+use std::io::{self, Write};
+
+use crossterm::{cursor, style::Print, QueueableCommand};
+use shellwords::split as shellwords_split;
+
+/// Converts every `\r\n` and `\r` line ending of the pasted `text` into `\n`, and removes its trailing new lines
+fn normalize_paste(text: &str) -> String {
+    let mut text = text.replace("\r\n", "\n").replace('\r', "\n");
+    text.truncate(text.trim_end_matches('\n').len());
+    text
+}
+
+/// Splits the normalized pasted `text` into a command per line, skipping blank lines. The given `input` is
+/// prepended to the first line, as the paste is inserted at the end of the input line
+fn paste_commands(input: &str, text: &str) -> Vec<String> {
+    format!("{input}{text}")
+        .split('\n')
+        .filter(|line| !line.trim().is_empty())
+        .map(String::from)
+        .collect()
+}
+
+impl<Ctx, W: Write> Repl<Ctx, W> {
+    /// Inserts pasted `text` into the input line. Pastes that span multiple lines are continued on new rows if
+    /// multi-line input is enabled, otherwise the user is asked to confirm running each line as its own command.
+    /// Pastes made while a confirmation is pending are appended to the pending commands
+    pub(super) fn paste(&mut self, text: &str) -> io::Result<()> {
+        let text = normalize_paste(text);
+
+        if !self.line.pending_paste.is_empty() {
+            self.line.pending_paste.extend(paste_commands("", &text));
+            return Ok(());
+        }
+
+        if !text.contains('\n') || self.line.multi_line_enabled {
+            return self.append_to_line(&text);
+        }
+
+        let mut commands = paste_commands(self.input(), &text);
+
+        match commands.len() {
+            0 => self.set_uneventful(),
+            1 => {
+                self.change_line(commands.pop().expect("len is 1"))?;
+            }
+            _ => self.line.pending_paste = commands,
+        }
+        Ok(())
+    }
+
+    /// Queues every pasted command to be submitted if the user `accept`ed the confirmation, otherwise the
+    /// pasted commands are discarded
+    pub(super) fn confirm_paste(&mut self, accept: bool) -> io::Result<EventLoop<Ctx, W>> {
+        let commands = std::mem::take(&mut self.line.pending_paste);
+        if !accept {
+            return Ok(EventLoop::Continue);
+        }
+
+        // The first command already contains the input line
+        self.clear_line()?;
+        self.queued_input.extend(commands);
+        Ok(self.process_queued_input()?.unwrap_or(EventLoop::Continue))
+    }
+
+    /// Returns the number of lines that are queued to be submitted, eg. lines of a confirmed multi-line paste
+    #[inline]
+    pub fn queued_input_len(&self) -> usize {
+        self.queued_input.len()
+    }
+
+    /// Submits the next queued line as if it was entered by the user, returning `None` if no lines are queued or
+    /// an [`InputHook`] is active. This method is used internally by [`Self::run`] / [`Self::spawn`] and is only
+    /// relevant if you are writing a manual impl of the main run eval print loop, where it must be called
+    /// after [`Self::render`] and before awaiting the next event.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// repl.render(&mut command_context)?;
+    ///
+    /// if let Some(queued) = repl.process_queued_input()? {
+    ///     general_event_process!(&mut repl, &mut command_context, queued: queued);
+    ///     continue;
+    /// }
+    /// ```
+    ///
+    /// [`InputHook`]: crate::line::input_hook::InputHook
+    pub fn process_queued_input(&mut self) -> io::Result<Option<EventLoop<Ctx, W>>> {
+        if self.input_hooked() {
+            return Ok(None);
+        }
+        let Some(line) = self.queued_input.pop_front() else {
+            return Ok(None);
+        };
+        self.submit_line(line).map(Some)
+    }
+
+    /// Replaces the input line with the given `line` and submits it. If invalid submissions are blocked and the
+    /// line contains known errors the line is left in the input line and all queued lines are discarded
+    pub(super) fn submit_line(&mut self, line: String) -> io::Result<EventLoop<Ctx, W>> {
+        self.change_line(line)?;
+        if self.line.block_invalid && !self.line.diagnostics.is_empty() {
+            self.queued_input.clear();
+            self.reject_submission()?;
+            return Ok(EventLoop::Continue);
+        }
+
+        // Display the submitted line, `enter_command` expects the line to already be printed
+        self.line.semantic_spans = self.semantic_spans();
+        self.term
+            .queue(cursor::MoveToColumn(0))?
+            .queue(Print(&self.line))?;
+        self.move_to_end(self.line_len(), false)?;
+        Ok(EventLoop::TryProcessInput(
+            shellwords_split(&self.enter_command()?).map_err(|_| ParseErr::MismatchedQuotes),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_endings_are_normalized() {
+        assert_eq!(normalize_paste("a\r\nb\rc\nd"), "a\nb\nc\nd");
+        assert_eq!(normalize_paste("echo hi\r\n\r\n"), "echo hi");
+        assert_eq!(normalize_paste("\r\n"), "");
+    }
+
+    #[test]
+    fn pasted_commands() {
+        assert_eq!(
+            paste_commands("", "echo a\n\n  \necho b"),
+            ["echo a", "echo b"]
+        );
+        // the paste is inserted at the end of the existing input
+        assert_eq!(paste_commands("echo ", "hi\nquit"), ["echo hi", "quit"]);
+        assert_eq!(paste_commands("echo ", "\nquit"), ["echo ", "quit"]);
+        assert!(paste_commands("", "\n \n").is_empty());
+    }
+}
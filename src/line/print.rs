@@ -1,5 +1,10 @@
 use crate::line::{
-    style::ansi_code::{RED, RESET},
+    layout::{ansi_display_width, ansi_segments, grapheme_width},
+    style::{
+        ansi_code::{RED, RESET},
+        ELLIPSIS,
+    },
+    theme::ColorSupport,
     {Repl, NEW_LINE},
 };
 
@@ -13,6 +18,7 @@ use crossterm::{
     terminal::{BeginSynchronizedUpdate, Clear, ClearType::FromCursorDown},
     QueueableCommand,
 };
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Queues a single line to be displayed on the given writer to normalize across targets.
 ///
@@ -41,8 +47,8 @@ where
 
 /// Queues a single color encoded line to be displayed on the given writer to normalize across targets.
 ///
-/// Only will color encode [`RED`] if the [`Repl`]'s line stylization is enabled. Appends `"\r\n"` to the
-/// end of the given input.
+/// Only will color encode [`RED`] if `stylize` is set and the `NO_COLOR` environment variable is not set.
+/// Appends `"\r\n"` to the end of the given input.
 ///
 /// Since repl-oxide requires full control over the terminal driver and enforces "Raw Mode" via [`build`],
 /// [`std::println!`] on UNIX systems does not display text as it normally would. This function will ensure
@@ -60,7 +66,7 @@ where
     W: Write,
     D: Display,
 {
-    if !stylize {
+    if !stylize || ColorSupport::detect() == ColorSupport::NoColor {
         return println(writer, print);
     }
 
@@ -100,6 +106,369 @@ where
     Ok(())
 }
 
+/// Message that can be printed above the input line, every type that implements [`Display`] is printed as a
+/// single line. Used as the message type of the channel returned by [`Repl::spawn`]
+///
+/// [`Repl::spawn`]: crate::line::Repl::spawn
+pub trait BackgroundMsg: Send + 'static {
+    fn print<Ctx, W: Write>(self, repl: &mut Repl<Ctx, W>) -> io::Result<()>;
+}
+
+impl<T: Display + Send + 'static> BackgroundMsg for T {
+    fn print<Ctx, W: Write>(self, repl: &mut Repl<Ctx, W>) -> io::Result<()> {
+        repl.println(self)
+    }
+}
+
+/// Horizontal alignment of the cells within a [`Table`] column
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Alignment {
+    #[default]
+    Left,
+    Right,
+    Center,
+}
+
+/// Formats headers and rows into aligned columns that fit within the width of the terminal. Cells that do not
+/// fit within their column are truncated with an ellipsis, or wrapped onto additional rows if
+/// [`with_wrapping`] is set. Escape sequences within cells do not count towards their width.
+///
+/// Print a table with [`Repl::print_table`], or when you do not have access to the repl handle format it with
+/// [`Table::render`] and print the result with [`print_lines`].
+///
+/// # Example
+///
+/// ```ignore
+/// let table = Table::new(["Name", "Size"])
+///     .with_row(["Cargo.toml", "1.2 KiB"])
+///     .with_row(["README.md", "6.8 KiB"])
+///     .with_alignment(1, Alignment::Right)
+///     .with_borders();
+///
+/// repl.print_table(&table)?;
+/// ```
+///
+/// [`with_wrapping`]: Table::with_wrapping
+#[derive(Clone, Debug, Default)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    alignments: Vec<Alignment>,
+    borders: bool,
+    wrap: bool,
+}
+
+/// Displayed text of a table cell, split into escape sequences and graphemes with their width
+enum Atom<'a> {
+    Escape(&'a str),
+    Text(&'a str, u16),
+}
+
+/// Converts a table cell into a single line
+fn cell_text<D: Display>(cell: D) -> String {
+    cell.to_string().replace(['\n', '\t'], " ")
+}
+
+/// Fits the given `cell` within `width` columns, returning each displayed row of the cell with its width
+pub(super) fn fit_cell(cell: &str, width: u16, wrap: bool) -> Vec<(String, u16)> {
+    let mut atoms = Vec::new();
+    for (range, escape) in ansi_segments(cell) {
+        if escape {
+            atoms.push(Atom::Escape(&cell[range]));
+            continue;
+        }
+        atoms.extend(
+            cell[range]
+                .graphemes(true)
+                .map(|grapheme| Atom::Text(grapheme, grapheme_width(grapheme))),
+        );
+    }
+
+    let total = atoms
+        .iter()
+        .map(|atom| match atom {
+            Atom::Escape(_) => 0,
+            Atom::Text(_, width) => *width,
+        })
+        .fold(0, u16::saturating_add);
+    if total <= width {
+        return vec![(String::from(cell), total)];
+    }
+
+    let styled = atoms.iter().any(|atom| matches!(atom, Atom::Escape(_)));
+
+    if !wrap {
+        let mut truncated = String::new();
+        let mut used = 0;
+        for atom in atoms {
+            match atom {
+                Atom::Escape(escape) => truncated.push_str(escape),
+                Atom::Text(_, grapheme_width) if used + grapheme_width >= width => break,
+                Atom::Text(grapheme, grapheme_width) => {
+                    truncated.push_str(grapheme);
+                    used += grapheme_width;
+                }
+            }
+        }
+        truncated.push(ELLIPSIS);
+        if styled {
+            truncated.push_str(RESET);
+        }
+        return vec![(truncated, used + 1)];
+    }
+
+    let mut rows = Vec::new();
+    // Escape sequences seen so far, re-applied at the start of every wrapped row
+    let mut active = String::new();
+    let mut start = 0;
+
+    loop {
+        let (mut end, mut used, mut last_space) = (start, 0, None);
+        while let Some(atom) = atoms.get(end) {
+            if let Atom::Text(grapheme, grapheme_width) = *atom {
+                if used + grapheme_width > width {
+                    break;
+                }
+                if grapheme == " " {
+                    last_space = Some(end);
+                }
+                used += grapheme_width;
+            }
+            end += 1;
+        }
+
+        // Prefer to wrap at the last space, always place at least one atom on each row
+        if end != atoms.len() {
+            end = last_space
+                .filter(|&i| i > start)
+                .unwrap_or(end.max(start + 1));
+        }
+
+        let mut row = active.clone();
+        let mut row_width = 0;
+        for atom in &atoms[start..end] {
+            match atom {
+                Atom::Escape(escape) => {
+                    row.push_str(escape);
+                    active.push_str(escape);
+                }
+                Atom::Text(grapheme, grapheme_width) => {
+                    row.push_str(grapheme);
+                    row_width += grapheme_width;
+                }
+            }
+        }
+        if styled {
+            row.push_str(RESET);
+        }
+        rows.push((row, row_width));
+
+        start = end;
+        while matches!(atoms.get(start), Some(Atom::Text(" ", _))) {
+            start += 1;
+        }
+        if start >= atoms.len() {
+            return rows;
+        }
+    }
+}
+
+impl Table {
+    /// Creates a new table with the given column `headers`, an empty iterator creates a table without a header
+    /// row
+    pub fn new<I, D>(headers: I) -> Self
+    where
+        I: IntoIterator<Item = D>,
+        D: Display,
+    {
+        Table {
+            headers: headers.into_iter().map(cell_text).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Appends a row to the table. Rows with fewer cells than there are columns are padded with empty cells
+    pub fn add_row<I, D>(&mut self, row: I)
+    where
+        I: IntoIterator<Item = D>,
+        D: Display,
+    {
+        self.rows.push(row.into_iter().map(cell_text).collect())
+    }
+
+    /// Appends a row to the table, see: [`Table::add_row`]
+    pub fn with_row<I, D>(mut self, row: I) -> Self
+    where
+        I: IntoIterator<Item = D>,
+        D: Display,
+    {
+        self.add_row(row);
+        self
+    }
+
+    /// Sets the alignment of the cells within the given zero based `column`, columns are left aligned by default
+    pub fn with_alignment(mut self, column: usize, alignment: Alignment) -> Self {
+        if self.alignments.len() <= column {
+            self.alignments.resize(column + 1, Alignment::default());
+        }
+        self.alignments[column] = alignment;
+        self
+    }
+
+    /// Draws a border around the table and between each of its columns
+    pub fn with_borders(mut self) -> Self {
+        self.borders = true;
+        self
+    }
+
+    /// Wraps cells that do not fit within their column onto additional rows instead of truncating them
+    pub fn with_wrapping(mut self) -> Self {
+        self.wrap = true;
+        self
+    }
+
+    /// Returns the width of each column so that the table fits within `width`. Columns that are narrower than
+    /// an equal share of the available width keep their width, the remaining width is split between the rest
+    fn column_widths(&self, columns: usize, width: u16) -> Vec<u16> {
+        let mut natural = vec![0; columns];
+        for row in std::iter::once(&self.headers).chain(&self.rows) {
+            for (column, cell) in row.iter().enumerate() {
+                natural[column] = natural[column].max(ansi_display_width(cell));
+            }
+        }
+
+        let separators = if self.borders {
+            3 * columns + 1
+        } else {
+            2 * (columns - 1)
+        };
+        let mut available = (width as usize).saturating_sub(separators);
+        if natural.iter().map(|&width| width as usize).sum::<usize>() <= available {
+            return natural;
+        }
+
+        let mut fits = vec![false; columns];
+        let mut open = columns;
+        loop {
+            let share = available / open;
+            let mut changed = false;
+            for (column, &width) in natural.iter().enumerate() {
+                if !fits[column] && width as usize <= share {
+                    fits[column] = true;
+                    available -= width as usize;
+                    open -= 1;
+                    changed = true;
+                }
+            }
+            if !changed || open == 0 {
+                break;
+            }
+        }
+
+        let (share, mut extra) = (available / open.max(1), available % open.max(1));
+        natural
+            .into_iter()
+            .zip(fits)
+            .map(|(width, fits)| {
+                if fits {
+                    return width;
+                }
+                let width = share + usize::from(extra > 0);
+                extra = extra.saturating_sub(1);
+                width.clamp(1, u16::MAX as usize) as u16
+            })
+            .collect()
+    }
+
+    /// Formats a single row of the table, a row can span multiple lines if its cells are wrapped
+    fn format_row(&self, row: &[String], widths: &[u16], lines: &mut Vec<String>) {
+        let cells = widths
+            .iter()
+            .enumerate()
+            .map(|(column, &width)| {
+                fit_cell(row.get(column).map_or("", String::as_str), width, self.wrap)
+            })
+            .collect::<Vec<_>>();
+        let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+
+        for i in 0..height {
+            let mut line = String::from(if self.borders { "│ " } else { "" });
+            for (column, cell) in cells.iter().enumerate() {
+                if column != 0 {
+                    line.push_str(if self.borders { " │ " } else { "  " });
+                }
+                let (text, used) = cell
+                    .get(i)
+                    .map_or(("", 0), |(text, used)| (text.as_str(), *used));
+                let padding = widths[column].saturating_sub(used) as usize;
+                let (left, right) = match self.alignments.get(column).copied().unwrap_or_default() {
+                    Alignment::Left => (0, padding),
+                    Alignment::Right => (padding, 0),
+                    Alignment::Center => (padding / 2, padding - padding / 2),
+                };
+                line.extend(std::iter::repeat(' ').take(left));
+                line.push_str(text);
+                line.extend(std::iter::repeat(' ').take(right));
+            }
+            if self.borders {
+                line.push_str(" │");
+            } else {
+                line.truncate(line.trim_end_matches(' ').len());
+            }
+            lines.push(line);
+        }
+    }
+
+    /// Returns a horizontal rule that spans the table
+    fn rule(&self, widths: &[u16], [left, middle, right]: [&str; 3]) -> String {
+        let (pad, separator) = if self.borders { (2, middle) } else { (0, "  ") };
+        let rule = widths
+            .iter()
+            .map(|&width| "─".repeat(width as usize + pad))
+            .collect::<Vec<_>>()
+            .join(separator);
+        if self.borders {
+            format!("{left}{rule}{right}")
+        } else {
+            rule
+        }
+    }
+
+    /// Formats the table to fit within `width` columns. Lines are separated by `'\n'` so the returned string
+    /// must be printed via [`print_lines`] or [`Repl::print_lines`]
+    pub fn render(&self, width: u16) -> String {
+        let columns = self
+            .rows
+            .iter()
+            .map(Vec::len)
+            .chain(std::iter::once(self.headers.len()))
+            .max()
+            .expect("iterator is not empty");
+        if columns == 0 {
+            return String::new();
+        }
+
+        let widths = self.column_widths(columns, width);
+        let mut lines = Vec::new();
+
+        if self.borders {
+            lines.push(self.rule(&widths, ["┌", "┬", "┐"]));
+        }
+        if !self.headers.is_empty() {
+            self.format_row(&self.headers, &widths, &mut lines);
+            lines.push(self.rule(&widths, ["├", "┼", "┤"]));
+        }
+        for row in &self.rows {
+            self.format_row(row, &widths, &mut lines);
+        }
+        if self.borders {
+            lines.push(self.rule(&widths, ["└", "┴", "┘"]));
+        }
+
+        lines.join("\n")
+    }
+}
+
 impl<Ctx, W: Write> Repl<Ctx, W> {
     /// Queues text to be displayed on the repl's writer to normalize across targets. Appends `"\r\n"` to the end
     /// of the given input. If you are looking to convert multiple line endings at once use:
@@ -114,13 +483,15 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
     ///
     /// [`build`]: crate::line::builder::ReplBuilder::build
     pub fn println<D: Display>(&mut self, print: D) -> io::Result<()> {
-        self.prep_for_background_msg()?;
-        println(&mut self.term, print)
+        if self.transcript.is_some() {
+            self.record_output(&print.to_string())?;
+        }
+        println(&mut self.background_writer()?, print)
     }
 
     /// Queues color encoded text to be displayed on the repl's writer to normalize across targets. Only will
-    /// color encode [`RED`] if the [`Repl`]'s line stylization is enabled. Appends `"\r\n"` to the end of the
-    /// given input.
+    /// color encode with the error style of the [`Theme`] if the [`Repl`]'s line stylization is enabled. Appends
+    /// `"\r\n"` to the end of the given input.
     ///
     /// Since repl-oxide requires full control over the terminal driver and enforces "Raw Mode" via [`build`],
     /// [`std::println!`] on UNIX systems does not display text as it normally would. This function will ensure
@@ -130,9 +501,14 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
     /// is _only_ used when the repl is busy.
     ///
     /// [`build`]: crate::line::builder::ReplBuilder::build
+    /// [`Theme`]: crate::line::theme::Theme
     pub fn eprintln<D: Display>(&mut self, print: D) -> io::Result<()> {
-        self.prep_for_background_msg()?;
-        eprintln(&mut self.term, print, self.line.style_enabled)
+        if !self.line.style_enabled {
+            return self.println(print);
+        }
+        let styled = format!("{}{print}{RESET}", self.line.palette.error);
+        self.record_output(&styled)?;
+        println(&mut self.background_writer()?, styled)
     }
 
     /// Queues text to be displayed on the repl's writer to normalize across targets. Replaces all new line
@@ -148,8 +524,25 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
     ///
     /// [`build`]: crate::line::builder::ReplBuilder::build
     pub fn print_lines<S: AsRef<str>>(&mut self, str: S) -> io::Result<()> {
-        self.prep_for_background_msg()?;
-        print_lines(&mut self.term, str)
+        self.record_output(str.as_ref())?;
+        print_lines(&mut self.background_writer()?, str)
+    }
+
+    /// Prints the given `table` sized to fit the current width of the terminal, see: [`Repl::print_lines`]
+    pub fn print_table(&mut self, table: &Table) -> io::Result<()> {
+        self.print_lines(table.render(self.term_size.0))
+    }
+
+    /// Returns the writer background messages are written into, messages are buffered while the pager opened
+    /// via [`Repl::page`] is displayed
+    pub(super) fn background_writer(&mut self) -> io::Result<&mut dyn Write> {
+        match self.pager {
+            Some(ref mut pager) => Ok(&mut pager.buffered),
+            None => {
+                self.prep_for_background_msg()?;
+                Ok(&mut self.term)
+            }
+        }
     }
 
     /// In almost all cases this is not the method you are looking for, [`Repl::println`], [`Repl::eprintln`], and
@@ -163,8 +556,75 @@ impl<Ctx, W: Write> Repl<Ctx, W> {
         }
         execute!(self.term, BeginSynchronizedUpdate)?;
         self.term.queue(cursor::Hide)?;
-        self.move_to_beginning(self.line_len())?;
+        self.move_to_beginning(self.cursor_len())?;
         self.term.queue(Clear(FromCursorDown))?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_keeps_natural_widths() {
+        let table = Table::new(["Name", "Size"])
+            .with_row(["Cargo.toml", "1.2 KiB"])
+            .with_row(["\x1b[1mREADME.md\x1b[0m", "16 B"])
+            .with_alignment(1, Alignment::Right);
+        assert_eq!(
+            table.render(80),
+            [
+                "Name           Size",
+                "──────────  ───────",
+                "Cargo.toml  1.2 KiB",
+                "\x1b[1mREADME.md\x1b[0m      16 B",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn table_truncates_to_width() {
+        let table = Table::new(["Name", "Size"])
+            .with_row(["Cargo.toml", "1.2 KiB"])
+            .with_alignment(1, Alignment::Right);
+        assert_eq!(
+            table.render(12),
+            ["Name    Size", "─────  ─────", "Carg…  1.2 …"].join("\n")
+        );
+    }
+
+    #[test]
+    fn narrow_columns_keep_their_width() {
+        let table = Table::new(["Key", "Value"])
+            .with_row(["a", "one two three"])
+            .with_borders()
+            .with_wrapping();
+        assert_eq!(
+            table.render(15),
+            [
+                "┌─────┬───────┐",
+                "│ Key │ Value │",
+                "├─────┼───────┤",
+                "│ a   │ one   │",
+                "│     │ two   │",
+                "│     │ three │",
+                "└─────┴───────┘",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn wrapped_cells_reapply_escapes() {
+        assert_eq!(
+            fit_cell("\x1b[31mab cd\x1b[0m", 3, true),
+            [
+                (format!("\x1b[31mab{RESET}"), 2),
+                (format!("\x1b[31mcd\x1b[0m{RESET}"), 2)
+            ]
+        );
+        assert!(Table::new(Vec::<String>::new()).render(10).is_empty());
+    }
+}
@@ -0,0 +1,339 @@
+use crate::line::{
+    layout::display_width,
+    print::{fit_cell, print_lines},
+    Repl,
+};
+
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    execute,
+    style::Print,
+    terminal::{BeginSynchronizedUpdate, Clear, ClearType::FromCursorDown},
+    QueueableCommand,
+};
+use strip_ansi::strip_ansi;
+use tokio::{
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    time::timeout,
+};
+
+const SPINNER_FRAMES: [char; 4] = ['-', '\\', '|', '/'];
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+const MAX_BAR_WIDTH: usize = 30;
+const MIN_BAR_WIDTH: usize = 5;
+
+enum Update {
+    Position(u64),
+    Increment(u64),
+    Message(String),
+    Finish(Option<String>),
+    Remove,
+}
+
+/// Handle to a progress bar or spinner displayed above the input line, created via [`Repl::add_progress`] or
+/// [`Repl::add_spinner`]. Handles are `Send` so they can be moved into background tasks, every update is sent
+/// to the repl through a channel and displayed the next time the repl is rendered.
+///
+/// Once complete call [`finish`] or [`finish_with_message`] to print the final state as a normal line.
+/// Dropping the handle before it is finished removes it without printing anything.
+///
+/// [`finish`]: ProgressHandle::finish
+/// [`finish_with_message`]: ProgressHandle::finish_with_message
+pub struct ProgressHandle {
+    id: u64,
+    sender: UnboundedSender<(u64, Update)>,
+    finished: bool,
+}
+
+impl ProgressHandle {
+    // Updates sent after the repl has been dropped have nowhere to be displayed, so send errors are ignored
+    fn send(&self, update: Update) {
+        let _ = self.sender.send((self.id, update));
+    }
+
+    /// Sets the current position of the progress bar, has no effect on spinners
+    pub fn set_position(&self, position: u64) {
+        self.send(Update::Position(position))
+    }
+
+    /// Advances the current position of the progress bar by `delta`, has no effect on spinners
+    pub fn inc(&self, delta: u64) {
+        self.send(Update::Increment(delta))
+    }
+
+    /// Sets the message displayed in front of the progress bar or after the spinner
+    pub fn set_message<S: Into<String>>(&self, message: S) {
+        self.send(Update::Message(message.into()))
+    }
+
+    /// Removes the progress bar or spinner and prints its final state as a normal line, progress bars are
+    /// displayed as complete
+    pub fn finish(mut self) {
+        self.finished = true;
+        self.send(Update::Finish(None))
+    }
+
+    /// Removes the progress bar or spinner and prints the given `message` in its place
+    pub fn finish_with_message<S: Into<String>>(mut self, message: S) {
+        self.finished = true;
+        self.send(Update::Finish(Some(message.into())))
+    }
+}
+
+impl Drop for ProgressHandle {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.send(Update::Remove)
+        }
+    }
+}
+
+struct Progress {
+    id: u64,
+    message: String,
+    position: u64,
+    /// `None` for spinners
+    total: Option<u64>,
+    started: Instant,
+}
+
+impl Progress {
+    /// Formats the progress bar or spinner to fit within a single row that is `columns` wide
+    fn format(&self, columns: u16) -> String {
+        let row = match self.total {
+            Some(total) => {
+                let position = self.position.min(total);
+                // A total of zero is always complete
+                let percent = (position as u128 * 100)
+                    .checked_div(total as u128)
+                    .unwrap_or(100);
+                let stats = format!("{position}/{total} {percent:>3}%");
+                let message_width = display_width(&strip_ansi(&self.message)) as usize;
+
+                // The '+ 4' is accounting for the brackets and spaces surrounding the bar
+                let bar_width = (columns as usize)
+                    .saturating_sub(message_width + stats.len() + 4)
+                    .min(MAX_BAR_WIDTH);
+                if bar_width < MIN_BAR_WIDTH {
+                    format!("{} {stats}", self.message)
+                } else {
+                    // `position` is at most `total` so `filled` is at most `bar_width`
+                    let filled = (position as u128 * bar_width as u128)
+                        .checked_div(total as u128)
+                        .map_or(bar_width, |filled| filled as usize);
+                    format!(
+                        "{} [{}{}] {stats}",
+                        self.message,
+                        "#".repeat(filled),
+                        "-".repeat(bar_width - filled)
+                    )
+                }
+            }
+            None => {
+                let frame = (self.started.elapsed().as_millis() / SPINNER_INTERVAL.as_millis())
+                    as usize
+                    % SPINNER_FRAMES.len();
+                format!("{} {}", SPINNER_FRAMES[frame], self.message)
+            }
+        };
+        fit_cell(&row, columns, false).swap_remove(0).0
+    }
+}
+
+/// Progress bars and spinners displayed above the input line
+pub(super) struct ProgressRows {
+    entries: Vec<Progress>,
+    /// Final lines of finished entries that are printed on the next render
+    finished: Vec<String>,
+    next_id: u64,
+    sender: UnboundedSender<(u64, Update)>,
+    receiver: UnboundedReceiver<(u64, Update)>,
+}
+
+impl Default for ProgressRows {
+    fn default() -> Self {
+        let (sender, receiver) = unbounded_channel();
+        ProgressRows {
+            entries: Vec::new(),
+            finished: Vec::new(),
+            next_id: 0,
+            sender,
+            receiver,
+        }
+    }
+}
+
+impl ProgressRows {
+    fn add(&mut self, message: String, total: Option<u64>) -> ProgressHandle {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.push(Progress {
+            id,
+            message,
+            position: 0,
+            total,
+            started: Instant::now(),
+        });
+        ProgressHandle {
+            id,
+            sender: self.sender.clone(),
+            finished: false,
+        }
+    }
+
+    fn apply(&mut self, (id, update): (u64, Update), columns: u16) {
+        let Some(i) = self.entries.iter().position(|entry| entry.id == id) else {
+            return;
+        };
+        let entry = &mut self.entries[i];
+        match update {
+            Update::Position(position) => entry.position = position,
+            Update::Increment(delta) => entry.position = entry.position.saturating_add(delta),
+            Update::Message(message) => entry.message = message,
+            Update::Finish(message) => {
+                let mut entry = self.entries.remove(i);
+                let line = message.unwrap_or_else(|| match entry.total {
+                    Some(total) => {
+                        entry.position = total;
+                        entry.format(columns)
+                    }
+                    None => entry.message,
+                });
+                self.finished.push(line);
+            }
+            Update::Remove => {
+                self.entries.remove(i);
+            }
+        }
+    }
+}
+
+impl<Ctx, W: Write> Repl<Ctx, W> {
+    /// Displays a progress bar with the given `message` above the input line, returning a handle used to update
+    /// it. The bar is complete once its position reaches `total`. See: [`ProgressHandle`]
+    ///
+    /// Progress rows are redrawn on every [`Repl::render`]. [`Repl::spawn`] renders as soon as an update is
+    /// received, custom read eval print loops can do the same by awaiting [`Repl::update_progress`].
+    ///
+    /// [`Repl::spawn`]: crate::line::Repl::spawn
+    pub fn add_progress<S: Into<String>>(&mut self, message: S, total: u64) -> ProgressHandle {
+        self.progress.add(message.into(), Some(total))
+    }
+
+    /// Displays a spinner with the given `message` above the input line, returning a handle used to update it.
+    /// See: [`Repl::add_progress`]
+    pub fn add_spinner<S: Into<String>>(&mut self, message: S) -> ProgressHandle {
+        self.progress.add(message.into(), None)
+    }
+
+    /// Returns the number of progress bars and spinners that are currently displayed
+    #[inline]
+    pub fn progress_len(&self) -> usize {
+        self.progress.entries.len()
+    }
+
+    /// Waits until a progress bar or spinner needs to be redrawn, then applies every pending update. Spinners
+    /// are animated while this future is awaited. This method is used internally by [`Repl::spawn`] and is only
+    /// relevant if you are writing a manual impl of the main run eval print loop, pending updates are always
+    /// applied by [`Repl::render`].
+    ///
+    /// This method is cancel safe so it can be used as a branch of `tokio::select!`, once it resolves the repl
+    /// must be rendered. A synchronized update is begun before this method returns so the redraw does not flicker,
+    /// it is ended by [`Repl::render`].
+    ///
+    /// [`Repl::spawn`]: crate::line::Repl::spawn
+    pub async fn update_progress(&mut self) -> io::Result<()> {
+        let update = if self
+            .progress
+            .entries
+            .iter()
+            .any(|entry| entry.total.is_none())
+        {
+            // Spinners are redrawn once the interval elapses
+            timeout(SPINNER_INTERVAL, self.progress.receiver.recv())
+                .await
+                .unwrap_or_default()
+        } else {
+            self.progress.receiver.recv().await
+        };
+
+        if let Some(update) = update {
+            self.progress.apply(update, self.term_size.0);
+        }
+        self.apply_progress_updates();
+        execute!(self.term, BeginSynchronizedUpdate)
+    }
+
+    /// Applies every progress update that was received since the last render
+    fn apply_progress_updates(&mut self) {
+        while let Ok(update) = self.progress.receiver.try_recv() {
+            self.progress.apply(update, self.term_size.0);
+        }
+    }
+
+    /// Prints the final lines of finished progress bars and spinners then formats the rows of the rest, must be
+    /// called once the previously displayed line has been cleared
+    pub(super) fn update_progress_rows(&mut self) -> io::Result<()> {
+        self.apply_progress_updates();
+        for line in std::mem::take(&mut self.progress.finished) {
+            self.record_output(&line)?;
+            print_lines(&mut self.term, line)?;
+        }
+
+        let columns = self.term_size.0;
+        self.line.progress_rows = self
+            .progress
+            .entries
+            .iter()
+            .map(|entry| entry.format(columns))
+            .collect();
+        Ok(())
+    }
+
+    /// Redraws the input line without the progress rows displayed above it, so they are not left in the
+    /// scrollback once the cursor moves past the input line. Expects the cursor to be at the end of the input
+    pub(super) fn clear_progress_rows(&mut self) -> io::Result<()> {
+        if self.line.progress_rows.is_empty() {
+            return Ok(());
+        }
+        if !self.cursor_at_start {
+            self.move_to_beginning(self.line_len())?;
+        }
+        self.line.progress_rows.clear();
+        self.term
+            .queue(Clear(FromCursorDown))?
+            .queue(Print(&self.line))?;
+        self.move_to_end(self.line_len(), false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(position: u64, total: u64) -> Progress {
+        Progress {
+            id: 0,
+            message: String::from("copy"),
+            position,
+            total: Some(total),
+            started: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn large_totals_do_not_overflow() {
+        assert_eq!(
+            bar(u64::MAX / 2, u64::MAX).format(60),
+            "copy [###----] 9223372036854775807/18446744073709551615  49%"
+        );
+        assert_eq!(
+            bar(u64::MAX, u64::MAX).format(60),
+            "copy [######] 18446744073709551615/18446744073709551615 100%"
+        );
+    }
+}
@@ -1,7 +1,16 @@
-use crate::line::LineData;
-use ansi_code::{BLUE, BOLD, GREY, RED_BOLD, RESET, YELLOW};
+use crate::line::{
+    completion::{Diagnostic, Signature, TokenKind},
+    layout::{display_width, grapheme_width},
+    multiline::CONTINUATION_PROMPT,
+    theme::{ColorSupport, Palette, Theme},
+    vars::var_refs,
+    LineData, Repl, NEW_LINE,
+};
+use ansi_code::{BOLD, RESET, UNDERLINE};
 
-use std::fmt::Display;
+use std::{borrow::Cow, fmt::Display, io::Write, ops::Range};
+
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Collection of ansi color codes
 pub mod ansi_code {
@@ -22,11 +31,12 @@ pub mod ansi_code {
     pub const CLEAR_LINE: &str = "\r\x1b[J";
 
     pub(super) const BOLD: &str = "\x1b[1m";
-    pub(super) const RED_BOLD: &str = concat!("\x1b[1;", RED_COLOR_CODE, "m");
+    pub(super) const UNDERLINE: &str = "\x1b[4m";
 }
 
-const QUOTES: [char; 2] = ['\'', '\"'];
+pub(super) const QUOTES: [char; 2] = ['\'', '\"'];
 const QUOTE_LEN: usize = QUOTES[0].len_utf8();
+pub(super) const ELLIPSIS: char = '…';
 
 #[derive(Default, PartialEq, Eq)]
 enum TextColor {
@@ -38,41 +48,129 @@ enum TextColor {
 }
 
 impl TextColor {
-    fn to_str(&self) -> &'static str {
+    fn to_str<'a>(&self, palette: &'a Palette) -> &'a str {
         match self {
-            TextColor::Yellow => YELLOW,
-            TextColor::Blue => BLUE,
-            TextColor::Grey => GREY,
+            TextColor::Yellow => &palette.command,
+            TextColor::Blue => &palette.quoted,
+            TextColor::Grey => &palette.flag,
             TextColor::White => RESET,
         }
     }
 }
 
-#[derive(Default)]
-struct FormatState {
+/// A styled section of the input line, see: [`Highlighter`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyledSpan {
+    /// Byte range of the section within the input line
+    pub range: Range<usize>,
+    /// ANSI escape codes that are applied to the section, eg. [`ansi_code::GREEN`]
+    pub style: Cow<'static, str>,
+}
+
+impl StyledSpan {
+    pub fn new(range: Range<usize>, style: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            range,
+            style: style.into(),
+        }
+    }
+}
+
+/// Highlighters define how the input line is styled. A custom highlighter can be set via
+/// [`ReplBuilder::with_highlighter`] or [`Repl::set_highlighter`], otherwise tokens are styled by their role
+/// within the [`CommandScheme`] if completion is set or by [`DefaultHighlighter`] if not
+///
+/// Highlighters can only style the input, they can not modify it. Returned spans are sorted and spans that
+/// overlap a previous span or do not lie on char boundaries of the input are discarded. Text not covered by
+/// a span is displayed without styling. Closures that take the input and return spans are also highlighters
+///
+/// [`ReplBuilder::with_highlighter`]: crate::line::ReplBuilder::with_highlighter
+/// [`Repl::set_highlighter`]: crate::line::Repl::set_highlighter
+/// [`CommandScheme`]: crate::completion::CommandScheme
+pub trait Highlighter: Send + Sync + 'static {
+    /// Returns the styled spans of the given `input`
+    fn highlight(&self, input: &str) -> Vec<StyledSpan>;
+}
+
+impl<T> Highlighter for T
+where
+    T: Fn(&str) -> Vec<StyledSpan> + Send + Sync + 'static,
+{
+    fn highlight(&self, input: &str) -> Vec<StyledSpan> {
+        self(input)
+    }
+}
+
+/// Colors the input line by token shape using the command, quoted, and flag styles of a [`Theme`]. The first
+/// token is styled as a command, quoted strings as quoted, and arguments as flags
+pub struct DefaultHighlighter {
+    palette: Palette,
+}
+
+impl DefaultHighlighter {
+    /// Highlighter that uses the given `theme`, colors are downgraded to what the terminal supports
+    pub fn new(theme: &Theme) -> Self {
+        Self {
+            palette: theme.palette(ColorSupport::detect()),
+        }
+    }
+}
+
+impl Default for DefaultHighlighter {
+    fn default() -> Self {
+        Self::new(&Theme::default())
+    }
+}
+
+impl Highlighter for DefaultHighlighter {
+    fn highlight(&self, input: &str) -> Vec<StyledSpan> {
+        stylize_input(input, &self.palette).0
+    }
+}
+
+struct FormatState<'a> {
+    palette: &'a Palette,
     curr_color: TextColor,
     open_quote: Option<(char, usize, usize)>,
     white_space_start: usize,
-    output: String,
+    /// Byte index of the end of the last pushed slice
+    pos: usize,
+    /// Byte index where `curr_color` was set
+    color_start: usize,
+    spans: Vec<StyledSpan>,
 }
 
-impl FormatState {
-    fn new() -> Self {
+impl<'a> FormatState<'a> {
+    fn new(palette: &'a Palette) -> Self {
         FormatState {
-            output: String::from(TextColor::default().to_str()),
-            ..Default::default()
+            palette,
+            curr_color: TextColor::default(),
+            open_quote: None,
+            white_space_start: 0,
+            pos: 0,
+            color_start: 0,
+            spans: Vec::new(),
         }
     }
 
     #[inline]
     fn set_color(&mut self, color: TextColor) {
-        self.output.push_str(color.to_str());
+        if self.curr_color != TextColor::White && self.pos > self.color_start {
+            self.spans.push(StyledSpan::new(
+                self.color_start..self.pos,
+                self.curr_color.to_str(self.palette).to_string(),
+            ));
+        }
         self.curr_color = color;
+        self.color_start = self.pos;
     }
 
+    /// The given `range` is the byte range of the pushed slice within the input being formatted
     #[inline]
-    fn push(&mut self, str: &str) {
-        self.output.push_str(str);
+    fn push(&mut self, range: Range<usize>) {
+        if !range.is_empty() {
+            self.pos = range.end;
+        }
     }
 
     #[inline]
@@ -89,8 +187,8 @@ impl FormatState {
     }
 
     #[inline]
-    fn close_quote(&mut self, input: &str, start: usize, token_len: usize) {
-        self.push(&input[start..start + token_len]);
+    fn close_quote(&mut self, start: usize, token_len: usize) {
+        self.push(start..start + token_len);
         self.open_quote = None;
         self.set_color(TextColor::White);
     }
@@ -99,30 +197,291 @@ impl FormatState {
 impl Display for LineData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{RESET}")?;
+        for row in &self.progress_rows {
+            write!(f, "{row}{RESET}{NEW_LINE}")?;
+        }
+        // Raw mode requires a carriage return for each line of a multi-line prompt
+        let prompt = if self.prompt.contains('\n') {
+            Cow::Owned(self.prompt.replace('\n', NEW_LINE))
+        } else {
+            Cow::Borrowed(self.prompt.as_str())
+        };
         if !self.style_enabled {
-            return write!(f, "{}{} {}", self.prompt, self.prompt_separator, self.input);
+            return write!(
+                f,
+                "{prompt}{} {}",
+                self.prompt_separator,
+                self.unstyled_input()
+            );
         }
-        let (stylized_input, mismatched_quotes) = stylize_input(&self.input);
+        let (input, invalid) = self.styled_input();
         write!(
             f,
-            "{BOLD}{}{}{}{RESET} {stylized_input}",
-            self.prompt.as_str(),
-            if self.err || mismatched_quotes { RED_BOLD } else { "" },
-            self.prompt_separator.as_str()
+            "{}{prompt}{RESET}{}{}{RESET} {input}",
+            self.palette.prompt,
+            self.separator_style(invalid),
+            self.prompt_separator.as_str(),
         )
     }
 }
 
-fn stylize_input(input: &str) -> (String, bool) {
-    let mut ctx = FormatState::new();
+impl LineData {
+    /// Formats the line with the given minimal `prompt` in place of the prompt and prompt separator
+    pub(super) fn transient(&self, prompt: &str) -> String {
+        if !self.style_enabled {
+            return format!("{RESET}{prompt} {}", self.unstyled_input());
+        }
+        let (input, invalid) = self.styled_input();
+        format!(
+            "{RESET}{}{prompt}{RESET} {input}",
+            self.separator_style(invalid)
+        )
+    }
+
+    /// Returns the input with a continuation prompt placed at the start of every row after the first
+    fn unstyled_input(&self) -> Cow<'_, str> {
+        if !self.input.contains('\n') {
+            return Cow::Borrowed(&self.input);
+        }
+        Cow::Owned(
+            self.input
+                .replace('\n', &format!("{NEW_LINE}{CONTINUATION_PROMPT} ")),
+        )
+    }
 
-    for mut token in input.split_whitespace() {
+    #[inline]
+    fn separator_style(&self, invalid: bool) -> &str {
+        if invalid {
+            &self.palette.error_separator
+        } else {
+            &self.palette.separator
+        }
+    }
+
+    /// Returns the styled input and if the input contains known errors
+    fn styled_input(&self) -> (String, bool) {
+        let palette = &self.palette;
+        let (spans, mismatched_quotes) = match (self.highlighter.as_deref(), &self.semantic_spans) {
+            (Some(highlighter), _) => (
+                highlighter.highlight(&self.input),
+                stylize_input(&self.input, palette).1,
+            ),
+            (None, Some(spans)) => (spans.clone(), stylize_input(&self.input, palette).1),
+            (None, None) => stylize_input(&self.input, palette),
+        };
+        let vars = if self.vars_enabled {
+            var_refs(&self.input).map(|var| var.span).collect()
+        } else {
+            Vec::new()
+        };
+        (
+            apply_styles(
+                &self.input,
+                spans,
+                self.diagnostics.first().map(Diagnostic::span),
+                &vars,
+                &palette.variable,
+                &format!("{NEW_LINE}{}{CONTINUATION_PROMPT}{RESET} ", palette.prompt),
+            ),
+            !self.diagnostics.is_empty() || mismatched_quotes,
+        )
+    }
+}
+
+impl<Ctx, W: Write> Repl<Ctx, W> {
+    /// Classifies the input line with the [`CommandScheme`] so every token is styled by its role rather than its
+    /// shape. Returns `None` if no scheme is set, a custom highlighter is set, or line stylization is disabled
+    ///
+    /// [`CommandScheme`]: crate::completion::CommandScheme
+    pub(super) fn semantic_spans(&mut self) -> Option<Vec<StyledSpan>> {
+        if !self.line.style_enabled || self.line.highlighter.is_some() || self.completion.is_empty()
+        {
+            return None;
+        }
+        let classified = self.classify();
+        let palette = &self.line.palette;
+        let quoted = |range: &Range<usize>| self.line.input[range.clone()].starts_with(QUOTES);
+        Some(
+            classified
+                .into_iter()
+                .filter_map(|(range, kind)| {
+                    let style = match kind {
+                        TokenKind::Command => &palette.command,
+                        TokenKind::Argument => &palette.flag,
+                        TokenKind::Value => &palette.value,
+                        TokenKind::UserInput => &palette.user_input,
+                        TokenKind::Invalid => &palette.invalid,
+                        TokenKind::Partial if quoted(&range) => &palette.quoted,
+                        TokenKind::Partial => return None,
+                    };
+                    Some(StyledSpan::new(range, style.clone()))
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Applies the given `spans` to the `input`, `underline` and `vars` are layered over the spans. Invalid spans
+/// are discarded so the displayed text always matches the `input`. Every new line within the `input` is
+/// displayed as the unstyled `new_row`
+fn apply_styles(
+    input: &str,
+    mut spans: Vec<StyledSpan>,
+    underline: Option<Range<usize>>,
+    vars: &[Range<usize>],
+    var_style: &str,
+    new_row: &str,
+) -> String {
+    spans.sort_by_key(|span| span.range.start);
+    let mut valid_from = 0;
+    spans.retain(|span| {
+        let valid = span.range.start >= valid_from
+            && span.range.start < span.range.end
+            && input.is_char_boundary(span.range.start)
+            && input.is_char_boundary(span.range.end);
+        if valid {
+            valid_from = span.range.end;
+        }
+        valid
+    });
+
+    let mut boundaries = spans
+        .iter()
+        .map(|span| &span.range)
+        .chain(&underline)
+        .chain(vars)
+        .flat_map(|range| [range.start, range.end])
+        .chain(input.match_indices('\n').flat_map(|(i, _)| [i, i + 1]))
+        .chain([0, input.len()])
+        .collect::<Vec<_>>();
+    boundaries.sort_unstable();
+    boundaries.dedup();
+
+    let mut output = String::with_capacity(input.len());
+    let mut prev_style = (None, false, false);
+    for bounds in boundaries.windows(2) {
+        let (start, end) = (bounds[0], bounds[1]);
+        if &input[start..end] == "\n" {
+            if prev_style != (None, false, false) {
+                output.push_str(RESET);
+                prev_style = (None, false, false);
+            }
+            output.push_str(new_row);
+            continue;
+        }
+        let style = (
+            spans
+                .iter()
+                .find(|span| span.range.contains(&start))
+                .map(|span| span.style.as_ref()),
+            vars.iter().any(|var| var.contains(&start)),
+            underline.as_ref().is_some_and(|span| span.contains(&start)),
+        );
+        if style != prev_style {
+            if prev_style != (None, false, false) {
+                output.push_str(RESET);
+            }
+            let (span_style, in_var, underlined) = style;
+            output.push_str(if in_var {
+                var_style
+            } else {
+                span_style.unwrap_or_default()
+            });
+            if underlined {
+                output.push_str(UNDERLINE);
+            }
+            prev_style = style;
+        }
+        output.push_str(&input[start..end]);
+    }
+    if prev_style != (None, false, false) {
+        output.push_str(RESET);
+    }
+    output
+}
+
+impl Signature {
+    /// Formats the signature to fit within the given `width`. If the signature is too long the visible window
+    /// is shifted so the current input stays in view. Unstyled if no `style` is given
+    pub(super) fn format(&self, width: usize, style: Option<&str>) -> String {
+        let column = |byte_i: usize| display_width(&self.text[..byte_i]) as usize;
+        let len = column(self.text.len());
+        let current_end = self
+            .current
+            .as_ref()
+            .map_or(0, |current| column(current.end));
+        let (start, end) = match width {
+            0..=1 => return String::new(),
+            _ if len <= width => (0, len),
+            _ if current_end < width => (0, width - 1),
+            _ => {
+                let start = current_end - (width - 2);
+                (
+                    start,
+                    if len - start < width {
+                        len
+                    } else {
+                        current_end
+                    },
+                )
+            }
+        };
+
+        let mut output = String::new();
+        if let Some(style) = style {
+            output.push_str(style);
+        }
+        if start != 0 {
+            output.push(ELLIPSIS);
+        }
+        let mut emphasized = false;
+        let mut col = 0;
+        for (i, grapheme) in self.text.grapheme_indices(true) {
+            let grapheme_start = col;
+            col += grapheme_width(grapheme) as usize;
+            // wide graphemes that straddle either edge of the window are left out
+            if grapheme_start < start || col > end {
+                continue;
+            }
+            let in_current = self
+                .current
+                .as_ref()
+                .is_some_and(|current| current.contains(&i));
+            if let Some(style) = style.filter(|_| in_current != emphasized) {
+                output.push_str(RESET);
+                output.push_str(if in_current { BOLD } else { style });
+                emphasized = in_current;
+            }
+            output.push_str(grapheme);
+        }
+        if let Some(style) = style.filter(|_| emphasized) {
+            output.push_str(RESET);
+            output.push_str(style);
+        }
+        if end != len {
+            output.push(ELLIPSIS);
+        }
+        if style.is_some() {
+            output.push_str(RESET);
+        }
+        output
+    }
+}
+
+/// Styles the input by token shape, returning the styled spans and if the input contains mismatched quotes
+fn stylize_input(input: &str, palette: &Palette) -> (Vec<StyledSpan>, bool) {
+    let mut ctx = FormatState::new(palette);
+
+    for token in input.split_whitespace() {
         let white_space_len = input[ctx.white_space_start..]
             .find(token)
             .expect("already found");
+        let token_start = ctx.white_space_start + white_space_len;
+        let token_end = token_start + token.len();
+        let mut push_start = token_start;
 
         let push_ws = |ctx: &mut FormatState| {
-            ctx.push(&input[ctx.white_space_start..ctx.white_space_start + white_space_len]);
+            ctx.push(ctx.white_space_start..token_start);
         };
 
         let advance_ws = |ctx: &mut FormatState| {
@@ -135,9 +494,9 @@ fn stylize_input(input: &str) -> (String, bool) {
                     let token_len = slice_data
                         .remainder
                         .map_or(token.len(), |_| slice_data.contains_quote.len());
-                    ctx.close_quote(input, start, open_quote_len + white_space_len + token_len);
+                    ctx.close_quote(start, open_quote_len + white_space_len + token_len);
                     if let Some(remainder) = slice_data.remainder {
-                        ctx.push(remainder);
+                        ctx.push(token_end - remainder.len()..token_end);
                     }
                 }
                 Some(slice_data) => {
@@ -162,7 +521,7 @@ fn stylize_input(input: &str) -> (String, bool) {
             if let Some(quote) = slice_data.open_quote {
                 let token_len = slice_data.remainder.map_or(token.len(), |rem| {
                     push_ws(&mut ctx);
-                    ctx.push(slice_data.contains_quote);
+                    ctx.push(token_start..token_start + slice_data.contains_quote.len());
                     ctx.set_color(TextColor::White);
                     rem.len()
                 });
@@ -172,13 +531,15 @@ fn stylize_input(input: &str) -> (String, bool) {
             }
 
             push_ws(&mut ctx);
-            ctx.push(slice_data.contains_quote);
+            ctx.push(token_start..token_start + slice_data.contains_quote.len());
 
             if ctx.curr_color != TextColor::White {
                 ctx.set_color(TextColor::White);
             }
 
-            token = slice_data.remainder.unwrap_or_default();
+            push_start = slice_data
+                .remainder
+                .map_or(token_end, |rem| token_end - rem.len());
         } else {
             push_ws(&mut ctx);
 
@@ -194,7 +555,7 @@ fn stylize_input(input: &str) -> (String, bool) {
             }
         }
 
-        ctx.push(token);
+        ctx.push(push_start..token_end);
 
         if ctx.curr_color != TextColor::White {
             ctx.set_color(TextColor::White);
@@ -206,13 +567,13 @@ fn stylize_input(input: &str) -> (String, bool) {
     let remainder = ctx
         .open_quote
         .map_or(ctx.white_space_start, |(_, start, _)| start);
-    ctx.push(&input[remainder..]);
+    ctx.push(remainder..input.len());
 
     if ctx.curr_color != TextColor::White {
         ctx.set_color(TextColor::White);
     }
 
-    (ctx.output, ctx.open_quote.is_some())
+    (ctx.spans, ctx.open_quote.is_some())
 }
 
 struct QuoteSlice<'a> {
@@ -296,3 +657,157 @@ fn parse_quoted_token(token: &str, mut open: Option<char>) -> Option<QuoteSlice<
         }
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlight(input: &str) -> (Vec<(&str, &str)>, bool) {
+        let palette = Theme::default().palette(ColorSupport::TrueColor);
+        let (spans, open_quote) = stylize_input(input, &palette);
+        let styles = spans
+            .into_iter()
+            .map(|span| {
+                let style = match span.style.as_ref() {
+                    style if style == palette.command => "command",
+                    style if style == palette.quoted => "quoted",
+                    style if style == palette.flag => "flag",
+                    style => panic!("unexpected style: {style:?}"),
+                };
+                (input[span.range].trim_start(), style)
+            })
+            .collect();
+        (styles, open_quote)
+    }
+
+    #[test]
+    fn default_highlighter_styles_by_token_shape() {
+        assert_eq!(
+            highlight("echo hello --loud -c 'a b' \"c\"d -1"),
+            (
+                vec![
+                    ("echo", "command"),
+                    ("--loud", "flag"),
+                    ("-c", "flag"),
+                    ("'a b'", "quoted"),
+                    ("\"c\"", "quoted"),
+                ],
+                false
+            )
+        );
+        assert_eq!(
+            highlight("  'quoted cmd' arg").0,
+            [("'quoted cmd'", "command")]
+        );
+        assert_eq!(highlight(""), (vec![], false));
+    }
+
+    #[test]
+    fn invalid_highlighter_spans_are_discarded() {
+        let spans = vec![
+            StyledSpan::new(4..7, "<b>"),
+            StyledSpan::new(0..3, "<a>"),
+            // overlaps the previous span
+            StyledSpan::new(2..4, "<x>"),
+            // splits `é`
+            StyledSpan::new(10..11, "<x>"),
+            StyledSpan::new(8..8, "<x>"),
+            StyledSpan::new(12..40, "<x>"),
+        ];
+        assert_eq!(
+            apply_styles("abc def gé", spans, None, &[], "", ""),
+            format!("<a>abc{RESET} <b>def{RESET} gé")
+        );
+    }
+
+    #[test]
+    fn underline_and_vars_layer_over_spans() {
+        let input = "echo $HOME";
+        let spans = vec![StyledSpan::new(0..4, "<cmd>")];
+        let vars = var_refs(input).map(|var| var.span).collect::<Vec<_>>();
+        assert_eq!(
+            apply_styles(input, spans, Some(2..6), &vars, "<var>", ""),
+            format!("<cmd>ec{RESET}<cmd>{UNDERLINE}ho{RESET}{UNDERLINE} {RESET}<var>{UNDERLINE}${RESET}<var>HOME{RESET}")
+        );
+    }
+
+    #[test]
+    fn default_highlighter_open_quotes() {
+        assert_eq!(
+            highlight("echo 'a b"),
+            (vec![("echo", "command"), ("'a b", "quoted")], true)
+        );
+        assert_eq!(
+            highlight("echo \"it's\" done"),
+            (vec![("echo", "command"), ("\"it's\"", "quoted")], false)
+        );
+    }
+
+    fn signature(text: &str, current: &str) -> Signature {
+        let start = text.find(current).expect("current is a part of text");
+        Signature {
+            text: String::from(text),
+            current: Some(start..start + current.len()),
+        }
+    }
+
+    #[test]
+    fn signature_fits_width() {
+        let sig = signature("roll [--sides <SIDES>]", "[--sides <SIDES>]");
+        assert_eq!(sig.format(40, None), "roll [--sides <SIDES>]");
+        assert_eq!(sig.format(12, None), "…s <SIDES>]");
+        assert_eq!(sig.format(1, None), "");
+        assert_eq!(
+            sig.format(40, Some("<dim>")),
+            format!("<dim>roll {RESET}{BOLD}[--sides <SIDES>]{RESET}<dim>{RESET}")
+        );
+    }
+
+    #[test]
+    fn signature_measures_display_width() {
+        // every `名` occupies two columns
+        let sig = signature("名名名名 <input> [--flag]", "<input>");
+        assert_eq!(sig.format(25, None), "名名名名 <input> [--flag]");
+        assert_eq!(sig.format(16, None), "…名名名 <input>…");
+        assert_eq!(sig.format(12, None), "…名 <input>…");
+        // the window starts within a `名`, so it is left out rather than split
+        assert_eq!(sig.format(11, None), "… <input>…");
+        for width in 2..30 {
+            assert!(display_width(&sig.format(width, None)) as usize <= width);
+        }
+    }
+
+    fn submitted_line(input: &str, style_enabled: bool) -> LineData {
+        LineData {
+            prompt: String::from("user@host ~/projects"),
+            prompt_separator: String::from("$"),
+            input: String::from(input),
+            style_enabled,
+            semantic_spans: Some(Vec::new()),
+            palette: Theme::default().palette(ColorSupport::TrueColor),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn transient_prompt_replaces_prompt() {
+        assert_eq!(
+            submitted_line("echo \\\nhi", false).transient(">"),
+            format!("{RESET}> echo \\{NEW_LINE}{CONTINUATION_PROMPT} hi")
+        );
+
+        let line = submitted_line("echo \\\nhi", true);
+        assert_eq!(
+            line.transient(">"),
+            format!(
+                "{RESET}{}>{RESET} echo \\{NEW_LINE}{}{CONTINUATION_PROMPT}{RESET} hi",
+                line.palette.separator, line.palette.prompt
+            )
+        );
+
+        let line = submitted_line("echo 'hi", true);
+        assert!(line
+            .transient(">")
+            .starts_with(&format!("{RESET}{}>{RESET} ", line.palette.error_separator)));
+    }
+}
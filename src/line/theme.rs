@@ -0,0 +1,408 @@
+use std::fmt::Write;
+
+/// The 16 standard colors as displayed by xterm, used to find the closest match when downgrading colors
+const ANSI_16_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Channel values of the 6x6x6 color cube within the 256 color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// A foreground color. Colors are downgraded to the closest color the terminal supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// One of the 16 standard colors, `0..=7` are the normal colors and `8..=15` are their bright variants
+    Ansi16(u8),
+    /// A color from the 256 color palette
+    Ansi256(u8),
+    /// 24-bit truecolor
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    fn to_rgb(self) -> (u8, u8, u8) {
+        match self {
+            Color::Rgb(r, g, b) => (r, g, b),
+            Color::Ansi16(i) | Color::Ansi256(i @ 0..=15) => ANSI_16_RGB[i as usize % 16],
+            Color::Ansi256(i @ 16..=231) => {
+                let i = i - 16;
+                (
+                    CUBE_LEVELS[(i / 36) as usize],
+                    CUBE_LEVELS[(i / 6 % 6) as usize],
+                    CUBE_LEVELS[(i % 6) as usize],
+                )
+            }
+            Color::Ansi256(i) => {
+                let gray = 8 + (i - 232) * 10;
+                (gray, gray, gray)
+            }
+        }
+    }
+
+    fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+        [(r1, r2), (g1, g2), (b1, b2)]
+            .into_iter()
+            .map(|(a, b)| (a as i32 - b as i32).pow(2) as u32)
+            .sum()
+    }
+
+    fn to_ansi_256(self) -> u8 {
+        let (r, g, b) = match self {
+            Color::Ansi256(i) => return i,
+            Color::Ansi16(i) => return i % 16,
+            Color::Rgb(r, g, b) => (r, g, b),
+        };
+        let cube_i = |v: u8| match v {
+            0..=47 => 0,
+            48..=114 => 1,
+            _ => (v - 35) / 40,
+        };
+        let cube = 16 + 36 * cube_i(r) + 6 * cube_i(g) + cube_i(b);
+        let avg = ((r as u16 + g as u16 + b as u16) / 3) as u8;
+        let gray = 232 + (avg.saturating_sub(3) / 10).min(23);
+
+        [cube, gray]
+            .into_iter()
+            .min_by_key(|&i| Self::distance(Color::Ansi256(i).to_rgb(), (r, g, b)))
+            .expect("array is not empty")
+    }
+
+    fn to_ansi_16(self) -> u8 {
+        if let Color::Ansi16(i) = self {
+            return i % 16;
+        }
+        let rgb = self.to_rgb();
+        (0..16)
+            .min_by_key(|&i| Self::distance(ANSI_16_RGB[i as usize], rgb))
+            .expect("range is not empty")
+    }
+
+    /// Returns the SGR foreground parameter of the given 16 color palette index
+    fn ansi_16_param(i: u8) -> u8 {
+        match i % 16 {
+            i @ 0..=7 => 30 + i,
+            i => 90 + i - 8,
+        }
+    }
+
+    /// Writes the SGR parameters of the color downgraded to the given `support`
+    fn write_params(self, support: ColorSupport, params: &mut String) {
+        let _ = match support {
+            ColorSupport::NoColor => return,
+            ColorSupport::Ansi16 => write!(params, ";{}", Self::ansi_16_param(self.to_ansi_16())),
+            ColorSupport::Ansi256 => match self {
+                Color::Ansi16(i) => write!(params, ";{}", Self::ansi_16_param(i)),
+                _ => write!(params, ";38;5;{}", self.to_ansi_256()),
+            },
+            ColorSupport::TrueColor => match self {
+                Color::Ansi16(i) => write!(params, ";{}", Self::ansi_16_param(i)),
+                Color::Ansi256(i) => write!(params, ";38;5;{i}"),
+                Color::Rgb(r, g, b) => write!(params, ";38;2;{r};{g};{b}"),
+            },
+        };
+    }
+}
+
+/// The level of color support of the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorSupport {
+    /// Only text attributes are displayed, eg. bold
+    NoColor,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Detects the color support of the terminal via the `NO_COLOR`, `COLORTERM`, and `TERM` environment
+    /// variables. Falls back to `Ansi256` when support can not be determined
+    pub fn detect() -> Self {
+        let env = |key| std::env::var(key).ok().filter(|value| !value.is_empty());
+
+        if env("NO_COLOR").is_some() {
+            return ColorSupport::NoColor;
+        }
+        if env("COLORTERM").is_some_and(|value| value == "truecolor" || value == "24bit")
+            || env("WT_SESSION").is_some()
+        {
+            return ColorSupport::TrueColor;
+        }
+        match env("TERM") {
+            Some(term) if term == "dumb" => ColorSupport::NoColor,
+            Some(term) if term == "linux" || term.ends_with("16color") => ColorSupport::Ansi16,
+            _ => ColorSupport::Ansi256,
+        }
+    }
+}
+
+/// The style of a single themed element
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    fg: Option<Color>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+    reverse: bool,
+}
+
+impl Style {
+    /// Style with no color or attributes set
+    pub const fn new() -> Self {
+        Self {
+            fg: None,
+            bold: false,
+            dim: false,
+            italic: false,
+            underline: false,
+            reverse: false,
+        }
+    }
+
+    pub const fn fg(mut self, color: Color) -> Self {
+        self.fg = Some(color);
+        self
+    }
+
+    pub const fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    pub const fn dim(mut self) -> Self {
+        self.dim = true;
+        self
+    }
+
+    pub const fn italic(mut self) -> Self {
+        self.italic = true;
+        self
+    }
+
+    pub const fn underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    /// Swaps the foreground and background colors
+    pub const fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    /// Returns the ANSI escape sequence of the style with its color downgraded to the given `support`. Returns
+    /// an empty string if there is nothing to display
+    pub fn to_ansi(&self, support: ColorSupport) -> String {
+        let mut params = String::new();
+        for (set, param) in [
+            (self.bold, ";1"),
+            (self.dim, ";2"),
+            (self.italic, ";3"),
+            (self.underline, ";4"),
+            (self.reverse, ";7"),
+        ] {
+            if set {
+                params.push_str(param);
+            }
+        }
+        if let Some(color) = self.fg {
+            color.write_params(support, &mut params);
+        }
+        match params.strip_prefix(';') {
+            Some(params) => format!("\x1b[{params}m"),
+            None => String::new(),
+        }
+    }
+}
+
+/// Styles of every element the repl displays. Set via [`ReplBuilder::with_theme`] or [`Repl::set_theme`]
+///
+/// ```
+/// use repl_oxide::theme::{Color, Style, Theme};
+///
+/// let theme = Theme {
+///     command: Style::new().fg(Color::Rgb(80, 200, 120)).bold(),
+///     flag: Style::new().fg(Color::Ansi256(244)),
+///     ..Default::default()
+/// };
+/// ```
+///
+/// [`ReplBuilder::with_theme`]: crate::line::ReplBuilder::with_theme
+/// [`Repl::set_theme`]: crate::line::Repl::set_theme
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub prompt: Style,
+    pub separator: Style,
+    pub right_prompt: Style,
+    /// Separator style used when the input line contains errors
+    pub error_separator: Style,
+    pub command: Style,
+    /// Style of arguments, eg. `--flag`
+    pub flag: Style,
+    pub quoted: Style,
+    /// Style of values that are valid for the current argument. Only used when a [`CommandScheme`] is set
+    ///
+    /// [`CommandScheme`]: crate::completion::CommandScheme
+    pub value: Style,
+    /// Style of inputs accepted by a user defined entry. Only used when a [`CommandScheme`] is set
+    ///
+    /// [`CommandScheme`]: crate::completion::CommandScheme
+    pub user_input: Style,
+    /// Style of tokens that are unknown or invalid in their position. Only used when a [`CommandScheme`] is
+    /// set
+    ///
+    /// [`CommandScheme`]: crate::completion::CommandScheme
+    pub invalid: Style,
+    pub variable: Style,
+    pub ghost_text: Style,
+    /// Style of the signature and diagnostic hints displayed under the input line
+    pub hint: Style,
+    /// Style of text printed with [`Repl::eprintln`]
+    ///
+    /// [`Repl::eprintln`]: crate::line::Repl::eprintln
+    pub error: Style,
+    /// Style of the "^C" marker appended to lines cleared with <kbd>Ctrl</kbd> + <kbd>C</kbd>
+    pub ctrl_c: Style,
+    /// Style of the status bar displayed at the bottom of the repl, see: [`StatusBar`]
+    ///
+    /// [`StatusBar`]: crate::line::StatusBar
+    pub status_bar: Style,
+    /// Styles of the level prefix of a [`LogMessage`]
+    ///
+    /// [`LogMessage`]: crate::line::LogMessage
+    pub log_trace: Style,
+    pub log_debug: Style,
+    pub log_info: Style,
+    pub log_warn: Style,
+    pub log_error: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            prompt: Style::new().bold(),
+            separator: Style::new().bold(),
+            right_prompt: Style::new().fg(Color::Ansi16(8)),
+            error_separator: Style::new().bold().fg(Color::Ansi16(1)),
+            command: Style::new().fg(Color::Ansi256(220)),
+            flag: Style::new().dim().fg(Color::Ansi16(7)),
+            quoted: Style::new().fg(Color::Ansi256(38)),
+            value: Style::new().fg(Color::Ansi256(79)),
+            user_input: Style::new().fg(Color::Ansi256(38)),
+            invalid: Style::new().fg(Color::Ansi16(1)),
+            variable: Style::new().fg(Color::Ansi16(5)),
+            ghost_text: Style::new().fg(Color::Ansi16(8)),
+            hint: Style::new().fg(Color::Ansi16(8)),
+            error: Style::new().fg(Color::Ansi16(1)),
+            ctrl_c: Style::new().fg(Color::Ansi16(1)),
+            status_bar: Style::new().reverse(),
+            log_trace: Style::new().fg(Color::Ansi16(5)),
+            log_debug: Style::new().fg(Color::Ansi16(4)),
+            log_info: Style::new().fg(Color::Ansi16(2)),
+            log_warn: Style::new().fg(Color::Ansi16(3)),
+            log_error: Style::new().bold().fg(Color::Ansi16(1)),
+        }
+    }
+}
+
+impl Theme {
+    /// Resolves every style into its escape sequence for the given `support`
+    pub(super) fn palette(&self, support: ColorSupport) -> Palette {
+        Palette {
+            prompt: self.prompt.to_ansi(support),
+            separator: self.separator.to_ansi(support),
+            right_prompt: self.right_prompt.to_ansi(support),
+            error_separator: self.error_separator.to_ansi(support),
+            command: self.command.to_ansi(support),
+            flag: self.flag.to_ansi(support),
+            quoted: self.quoted.to_ansi(support),
+            value: self.value.to_ansi(support),
+            user_input: self.user_input.to_ansi(support),
+            invalid: self.invalid.to_ansi(support),
+            variable: self.variable.to_ansi(support),
+            ghost_text: self.ghost_text.to_ansi(support),
+            hint: self.hint.to_ansi(support),
+            error: self.error.to_ansi(support),
+            ctrl_c: self.ctrl_c.to_ansi(support),
+            status_bar: self.status_bar.to_ansi(support),
+            log_trace: self.log_trace.to_ansi(support),
+            log_debug: self.log_debug.to_ansi(support),
+            log_info: self.log_info.to_ansi(support),
+            log_warn: self.log_warn.to_ansi(support),
+            log_error: self.log_error.to_ansi(support),
+        }
+    }
+}
+
+/// A [`Theme`] resolved into escape sequences
+#[derive(Debug, Clone)]
+pub(super) struct Palette {
+    pub(super) prompt: String,
+    pub(super) separator: String,
+    pub(super) right_prompt: String,
+    pub(super) error_separator: String,
+    pub(super) command: String,
+    pub(super) flag: String,
+    pub(super) quoted: String,
+    pub(super) value: String,
+    pub(super) user_input: String,
+    pub(super) invalid: String,
+    pub(super) variable: String,
+    pub(super) ghost_text: String,
+    pub(super) hint: String,
+    pub(super) error: String,
+    pub(super) ctrl_c: String,
+    pub(super) status_bar: String,
+    pub(super) log_trace: String,
+    pub(super) log_debug: String,
+    pub(super) log_info: String,
+    pub(super) log_warn: String,
+    pub(super) log_error: String,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Theme::default().palette(ColorSupport::Ansi256)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ansi_16_params_wrap_out_of_range_indices() {
+        for support in [
+            ColorSupport::Ansi16,
+            ColorSupport::Ansi256,
+            ColorSupport::TrueColor,
+        ] {
+            for (color, expected) in [
+                (1, ";31"),
+                (9, ";91"),
+                (16, ";30"),
+                (23, ";37"),
+                (24, ";90"),
+            ] {
+                let mut params = String::new();
+                Color::Ansi16(color).write_params(support, &mut params);
+                assert_eq!(params, expected, "Ansi16({color}) with {support:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use crate::line::{log::format_timestamp, Repl};
+
+use std::{
+    borrow::Cow,
+    fmt::Write as _,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use strip_ansi::strip_ansi;
+
+const INPUT_MARKER: &str = "> ";
+
+/// Converts the number of days since the unix epoch into the `(year, month, day)` of the date, see:
+/// <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months are counted from March so the leap day is the last day of the year
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    (year_of_era + era * 400 + u64::from(month <= 2), month, day)
+}
+
+/// Formats the given `time` as an RFC 3339 timestamp in UTC, eg. `2024-02-29T23:59:59Z`
+fn format_rfc3339(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (year, month, day) = civil_from_days(secs / 86_400);
+    format!("{year:04}-{month:02}-{day:02}T{}Z", format_timestamp(time))
+}
+
+/// File that submitted lines and printed output are appended to
+pub(super) struct Transcript {
+    file: File,
+    strip_ansi: bool,
+}
+
+impl Transcript {
+    pub(super) fn open(path: &Path, strip_ansi: bool) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut transcript = Transcript { file, strip_ansi };
+        transcript.write_chunk("", "--- recording started ---")?;
+        Ok(transcript)
+    }
+
+    /// Writes every line of `text` prefixed with the current time and the given `marker`, the chunk is
+    /// written with a single call so it is not interleaved with output of other processes
+    fn write_chunk(&mut self, marker: &str, text: &str) -> io::Result<()> {
+        let text = if self.strip_ansi {
+            Cow::Owned(strip_ansi(text))
+        } else {
+            Cow::Borrowed(text)
+        };
+        let timestamp = format_rfc3339(SystemTime::now());
+
+        let mut chunk = String::new();
+        for line in text.trim_end_matches(['\r', '\n']).split('\n') {
+            let _ = writeln!(
+                chunk,
+                "[{timestamp}] {marker}{}",
+                line.trim_end_matches('\r')
+            );
+        }
+        self.file.write_all(chunk.as_bytes())
+    }
+}
+
+impl<Ctx, W: Write> Repl<Ctx, W> {
+    /// Returns if a transcript is currently being recorded
+    #[inline]
+    pub fn transcript_enabled(&self) -> bool {
+        self.transcript.is_some()
+    }
+
+    /// Starts recording a transcript of the session into the file at the given `path`, eg. from a `record`
+    /// command. The file is created if it does not exist, otherwise the transcript is appended to it. If a
+    /// transcript is already being recorded it is stopped first.
+    ///
+    /// Every submitted line and all output printed via [`Repl::println`], [`Repl::eprintln`],
+    /// [`Repl::print_lines`], [`Repl::log`], and [`Repl::page`] is written to the file, as well as the final
+    /// lines of finished progress bars and spinners. Each line is prefixed with the RFC 3339 timestamp of when
+    /// it was recorded in UTC. Submitted lines are marked with a leading `"> "`. Ghost text, re-renders of the
+    /// input line, and cursor movement are never recorded, neither is text written into the terminal directly.
+    /// Set `strip_ansi` to remove all ANSI escape codes from the recorded text.
+    ///
+    /// If writing to the file fails while recording, the transcript is stopped and the error is printed once
+    /// via [`Repl::eprintln`]. The error is not returned to the caller of the method that printed the output.
+    pub fn start_transcript<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        strip_ansi: bool,
+    ) -> io::Result<()> {
+        self.stop_transcript()?;
+        self.transcript = Some(Transcript::open(path.as_ref(), strip_ansi)?);
+        Ok(())
+    }
+
+    /// Stops recording the current transcript, has no effect if no transcript is being recorded
+    pub fn stop_transcript(&mut self) -> io::Result<()> {
+        match self.transcript.take() {
+            Some(mut transcript) => transcript.write_chunk("", "--- recording stopped ---"),
+            None => Ok(()),
+        }
+    }
+
+    /// Records the submitted `line` if a transcript is being recorded
+    pub(super) fn record_input(&mut self, line: &str) -> io::Result<()> {
+        self.record(INPUT_MARKER, line)
+    }
+
+    /// Records the printed `output` if a transcript is being recorded
+    pub(super) fn record_output(&mut self, output: &str) -> io::Result<()> {
+        self.record("", output)
+    }
+
+    /// Stops the transcript if writing to it fails, only errors writing to the terminal are returned
+    fn record(&mut self, marker: &str, text: &str) -> io::Result<()> {
+        let Some(ref mut transcript) = self.transcript else {
+            return Ok(());
+        };
+        if let Err(err) = transcript.write_chunk(marker, text) {
+            self.transcript = None;
+            self.eprintln(format_args!("Transcript stopped, failed to write: {err}"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_722), (2023, 12, 31));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+    }
+
+    #[test]
+    fn rfc3339_timestamps() {
+        assert_eq!(format_rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            format_rfc3339(UNIX_EPOCH + Duration::from_secs(1_709_251_199)),
+            "2024-02-29T23:59:59Z"
+        );
+    }
+}
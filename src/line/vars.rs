@@ -0,0 +1,272 @@
+use crate::line::{
+    completion::{quote_recommendation, DOUBLE_QUOTE_ESCAPES},
+    style::QUOTES,
+    Repl,
+};
+
+use std::{borrow::Cow, collections::BTreeSet, io::Write, ops::Range};
+
+/// A reference to a variable found within the input line, eg. `$NAME` or `${NAME}`
+pub(super) struct VarRef<'a> {
+    /// Byte range of the entire reference, including the `$` and braces
+    pub(super) span: Range<usize>,
+    pub(super) name: &'a str,
+    /// If the reference is located within double quotes
+    in_quotes: bool,
+}
+
+#[inline]
+fn is_name_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
+}
+
+/// Names must start with a letter or an underscore and only contain alphanumeric chars and underscores
+#[inline]
+fn is_valid_name(name: &str) -> bool {
+    name.chars().next().is_some_and(|ch| !ch.is_ascii_digit()) && name.chars().all(is_name_char)
+}
+
+/// Finds the byte index of every `$` that can start a variable reference, paired with if the `$` is located
+/// within double quotes. `$` within single quotes or escaped with a backslash are skipped
+fn dollar_indices(line: &str) -> Vec<(usize, bool)> {
+    let mut indices = Vec::new();
+    let mut open_quote = None;
+    let mut escaped = false;
+
+    for (i, ch) in line.char_indices() {
+        if std::mem::take(&mut escaped) {
+            continue;
+        }
+        match open_quote {
+            Some(quote) if ch == quote => open_quote = None,
+            Some('\'') => (),
+            _ if ch == '\\' => escaped = true,
+            _ if ch == '$' => indices.push((i, open_quote.is_some())),
+            Some(_) => (),
+            None if QUOTES.contains(&ch) => open_quote = Some(ch),
+            None => (),
+        }
+    }
+
+    indices
+}
+
+/// Returns all complete variable references found within the given `line`
+pub(super) fn var_refs(line: &str) -> impl Iterator<Item = VarRef<'_>> {
+    dollar_indices(line)
+        .into_iter()
+        .filter_map(|(i, in_quotes)| {
+            let rest = &line[i + 1..];
+            let (name, len) = match rest.strip_prefix('{') {
+                Some(braced) => braced.find('}').map(|end| (&braced[..end], end + 2))?,
+                None => {
+                    let len = rest.find(|ch| !is_name_char(ch)).unwrap_or(rest.len());
+                    (&rest[..len], len)
+                }
+            };
+            is_valid_name(name).then_some(VarRef {
+                span: i..i + 1 + len,
+                name,
+                in_quotes,
+            })
+        })
+}
+
+/// Returns the byte index of the `$`, if the reference is braced, and the partial name of a variable reference
+/// that is being typed at the end of the given `line`
+pub(super) fn trailing_var_ref(line: &str) -> Option<(usize, bool, &str)> {
+    let (i, _) = dollar_indices(line).pop()?;
+    let rest = &line[i + 1..];
+    let (braced, partial) = rest
+        .strip_prefix('{')
+        .map_or((false, rest), |partial| (true, partial));
+    (partial.chars().all(is_name_char) && !partial.starts_with(|ch: char| ch.is_ascii_digit()))
+        .then_some((i, braced, partial))
+}
+
+/// Substitutes every reference of a known variable within the given `line` with its value. References within
+/// single quotes, escaped with a backslash, or to unknown variables are left untouched. Values are escaped so
+/// they are always parsed as a single literal token by `shellwords_split`
+fn expand<'a, F>(line: &'a str, lookup: F) -> Cow<'a, str>
+where
+    F: Fn(&str) -> Option<Cow<'a, str>>,
+{
+    let mut expanded = String::new();
+    let mut copied_till = 0;
+
+    for var in var_refs(line) {
+        let Some(value) = lookup(var.name) else {
+            continue;
+        };
+        expanded.push_str(&line[copied_till..var.span.start]);
+        if var.in_quotes {
+            for ch in value.chars() {
+                if DOUBLE_QUOTE_ESCAPES.contains(&ch) {
+                    expanded.push('\\');
+                }
+                expanded.push(ch);
+            }
+        } else {
+            expanded.push_str(&quote_recommendation(&value, None));
+        }
+        copied_till = var.span.end;
+    }
+
+    if copied_till == 0 {
+        return Cow::Borrowed(line);
+    }
+
+    expanded.push_str(&line[copied_till..]);
+    Cow::Owned(expanded)
+}
+
+/// Returns the names of all environment variables that can be referenced
+pub(super) fn env_var_names() -> Vec<String> {
+    std::env::vars_os()
+        .filter_map(|(name, _)| name.into_string().ok())
+        .filter(|name| is_valid_name(name))
+        .collect()
+}
+
+impl<Ctx, W: Write> Repl<Ctx, W> {
+    /// Returns if variable expansion is currently enabled
+    #[inline]
+    pub fn variable_expansion_enabled(&self) -> bool {
+        self.line.vars_enabled
+    }
+
+    /// Enables the expansion of `$NAME` and `${NAME}` references within submitted lines. Values are looked up
+    /// within the repl's variables first, then the process environment. See: [`Self::set_var`]
+    #[inline]
+    pub fn enable_variable_expansion(&mut self) {
+        self.line.vars_enabled = true
+    }
+
+    /// Disables variable expansion
+    #[inline]
+    pub fn disable_variable_expansion(&mut self) {
+        self.line.vars_enabled = false
+    }
+
+    /// Sets a variable that is scoped to the repl, returning the previous value if one existed. Repl variables
+    /// take priority over environment variables of the same name. Names must start with a letter or an
+    /// underscore and only contain alphanumeric chars and underscores to be able to be referenced
+    pub fn set_var(&mut self, name: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        self.vars.insert(name.into(), value.into())
+    }
+
+    /// Removes a variable that is scoped to the repl, returning its value if it existed
+    pub fn unset_var(&mut self, name: &str) -> Option<String> {
+        self.vars.remove(name)
+    }
+
+    /// Returns the value of a variable that is scoped to the repl
+    #[inline]
+    pub fn var(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(String::as_str)
+    }
+
+    /// Returns the value of a variable, repl variables take priority over environment variables
+    fn lookup_var(&self, name: &str) -> Option<Cow<'_, str>> {
+        self.vars
+            .get(name)
+            .map(|value| Cow::Borrowed(value.as_str()))
+            .or_else(|| std::env::var(name).ok().map(Cow::Owned))
+    }
+
+    /// Returns the names of all repl variables and the given `env_names` that start with the given `prefix` in
+    /// sorted order
+    pub(super) fn var_names(&self, prefix: &str, env_names: &[String]) -> Vec<String> {
+        self.vars
+            .keys()
+            .filter(|name| is_valid_name(name))
+            .chain(env_names)
+            .filter(|name| name.starts_with(prefix))
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Expands variable references within the given `line` if variable expansion is enabled
+    pub(super) fn expand_vars<'a>(&'a self, line: &'a str) -> Cow<'a, str> {
+        if !self.line.vars_enabled {
+            return Cow::Borrowed(line);
+        }
+        expand(line, |name| self.lookup_var(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<Cow<'static, str>> {
+        match name {
+            "NAME" => Some(Cow::Borrowed("world")),
+            "SPACED" => Some(Cow::Borrowed("a b")),
+            "QUOTE" => Some(Cow::Borrowed("say \"hi\" $5")),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expansion() {
+        let cases = [
+            ("echo $NAME", "echo world"),
+            ("echo ${NAME}s", "echo worlds"),
+            ("echo $NAMEs", "echo $NAMEs"),
+            ("echo $MISSING $NAME", "echo $MISSING world"),
+            ("echo '$NAME'", "echo '$NAME'"),
+            (r"echo \$NAME", r"echo \$NAME"),
+            ("echo \"hi $NAME\"", "echo \"hi world\""),
+            ("echo $SPACED", "echo \"a b\""),
+            ("echo \"$QUOTE\"", r#"echo "say \"hi\" \$5""#),
+            ("echo $1 ${} $", "echo $1 ${} $"),
+        ];
+
+        for (line, expected) in cases {
+            assert_eq!(expand(line, lookup), expected, "expanding: {line}");
+        }
+    }
+
+    #[test]
+    fn expanded_values_are_single_tokens() {
+        let expanded = expand("echo $QUOTE \"$QUOTE\" $SPACED", lookup);
+        assert_eq!(
+            shellwords::split(&expanded).unwrap(),
+            ["echo", "say \"hi\" $5", "say \"hi\" $5", "a b"]
+        );
+    }
+
+    #[test]
+    fn unchanged_lines_are_borrowed() {
+        assert!(matches!(expand("echo hello", lookup), Cow::Borrowed(_)));
+        assert!(matches!(expand("echo $MISSING", lookup), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn references() {
+        let refs = var_refs("a $ONE \"${TWO}\" '$THREE' $_4")
+            .map(|var| (var.name, var.span, var.in_quotes))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            refs,
+            [
+                ("ONE", 2..6, false),
+                ("TWO", 8..14, true),
+                ("_4", 25..28, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn trailing_references() {
+        assert_eq!(trailing_var_ref("echo $NA"), Some((5, false, "NA")));
+        assert_eq!(trailing_var_ref("echo ${NA"), Some((5, true, "NA")));
+        assert_eq!(trailing_var_ref("echo $"), Some((5, false, "")));
+        assert_eq!(trailing_var_ref("echo $NAME "), None);
+        assert_eq!(trailing_var_ref("echo $1"), None);
+        assert_eq!(trailing_var_ref("echo '$NA"), None);
+    }
+}
@@ -17,6 +17,16 @@
 /// }
 /// ```
 ///
+/// Lines queued to be submitted, eg. the lines of a confirmed multi-line paste, are processed with the
+/// `queued:` form of the macro. See: [`Repl::process_queued_input`]
+///
+/// ```ignore
+/// if let Some(queued) = repl.process_queued_input()? {
+///     general_event_process!(&mut repl, &mut command_context, queued: queued);
+///     continue;
+/// }
+/// ```
+///
 /// This macro requires you to implement [`Executor`] on your `Ctx`.
 ///
 /// Internally uses tracing's [`error!`] to log any errors that a user created [`AsyncCallback`] produces.
@@ -28,14 +38,15 @@
 ///
 /// [`AsyncCallback`]: crate::line::input_hook::AsyncCallback
 /// [`Executor`]: crate::executor::Executor
+/// [`Repl::process_queued_input`]: crate::line::Repl::process_queued_input
 /// [`InputHook`]: crate::line::input_hook::InputHook
 /// [`Event`]: <https://docs.rs/crossterm/latest/crossterm/event/enum.Event.html>
 /// [`error!`]: <https://docs.rs/tracing/latest/tracing/macro.error.html>
 /// [`trace!`]: <https://docs.rs/tracing/latest/tracing/macro.trace.html>
 #[macro_export]
 macro_rules! general_event_process {
-    ($repl:expr, $ctx:expr, $event_result:expr) => {
-        match $repl.process_input_event($ctx, $event_result?)? {
+    ($repl:expr, $ctx:expr, queued: $event_loop:expr) => {
+        match $event_loop {
             $crate::EventLoop::Continue => (),
             $crate::EventLoop::Break => break,
             $crate::EventLoop::AsyncCallback(callback) => {
@@ -61,4 +72,11 @@ macro_rules! general_event_process {
             }
         }
     };
+    ($repl:expr, $ctx:expr, $event_result:expr) => {
+        $crate::general_event_process!(
+            $repl,
+            $ctx,
+            queued: $repl.process_input_event($ctx, $event_result?)?
+        )
+    };
 }
@@ -17,6 +17,11 @@ impl<Ctx: Executor<W>, W: Write> Repl<Ctx, W> {
             self.clear_unwanted_inputs(&mut reader).await?;
             self.render(ctx)?;
 
+            if let Some(queued) = self.process_queued_input()? {
+                general_event_process!(self, ctx, queued: queued);
+                continue;
+            }
+
             if let Some(event_result) = reader.next().await {
                 general_event_process!(self, ctx, event_result)
             }
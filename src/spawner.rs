@@ -1,10 +1,11 @@
-use crate::{executor::Executor, general_event_process, line::Repl};
-
-use std::{
-    fmt::Display,
-    io::{self, Write},
+use crate::{
+    executor::Executor,
+    general_event_process,
+    line::{BackgroundMsg, Repl},
 };
 
+use std::io::{self, Write};
+
 use crossterm::event::EventStream;
 use tokio::{sync::mpsc::Sender, task::JoinHandle};
 use tokio_stream::StreamExt;
@@ -19,6 +20,8 @@ where
 
     /// Spawns the repl, returning you a [`tokio::sync::mpsc::Sender`] as a handle to your terminal output
     /// stream. You must use this channel anytime you need to display background messages to the terminal.
+    /// Any type that implements `Display` can be sent, send a [`LogMessage`] to display messages with a level
+    /// that can be filtered at runtime.
     ///
     /// Generally for advanced cases it is recommended to write your own read eval print loop over an
     /// [`EventStream`] this way will allow for deeper customization, make it easier to spot potential
@@ -29,15 +32,16 @@ where
     /// into dead locks if the repl thread tries to access the mutex at the same time as your own main
     /// thread. Using an async aware [`tokio::sync::Mutex`] should avoid dead lock scenarios
     ///
+    /// [`LogMessage`]: crate::line::LogMessage
     /// [basic_custom.rs]: <https://github.com/WardLordRuby/repl-oxide/blob/main/examples/basic_custom.rs>
     /// [`EventStream`]: <https://docs.rs/crossterm/0.28.1/crossterm/event/struct.EventStream.html>
     /// [`tokio::sync::Mutex`]: <https://docs.rs/tokio/latest/tokio/sync/struct.Mutex.html>
     /// [`tokio::sync::mpsc::Sender`]: <https://docs.rs/tokio/latest/tokio/sync/mpsc/struct.Sender.html>
     pub fn spawn<M>(mut self, mut ctx: Ctx) -> (JoinHandle<io::Result<()>>, Sender<M>)
     where
-        M: Display + Send + 'static,
+        M: BackgroundMsg,
     {
-        let (msg_tx, mut msg_rx) = tokio::sync::mpsc::channel(50);
+        let (msg_tx, mut msg_rx) = tokio::sync::mpsc::channel::<M>(50);
 
         let repl_handle = tokio::spawn(async move {
             let mut reader = EventStream::new();
@@ -46,6 +50,11 @@ where
                 self.clear_unwanted_inputs(&mut reader).await?;
                 self.render(&mut ctx)?;
 
+                if let Some(queued) = self.process_queued_input()? {
+                    general_event_process!(&mut self, &mut ctx, queued: queued);
+                    continue;
+                }
+
                 tokio::select! {
                     biased;
 
@@ -54,8 +63,10 @@ where
                     }
 
                     Some(msg) = msg_rx.recv() => {
-                        self.println(msg)?
+                        msg.print(&mut self)?
                     }
+
+                    result = self.update_progress() => result?
                 }
             }
 
@@ -0,0 +1,192 @@
+use crate::line::{Level, LogMessage, SharedLevel};
+
+use std::fmt::{self, Write};
+
+use tokio::sync::mpsc::Sender;
+use tracing::{
+    field::{Field, Visit},
+    Event, Subscriber,
+};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// [`Layer`] that forwards every tracing event as a [`LogMessage`] into a channel so it can be printed above
+/// the input line
+///
+/// Subscribers such as `tracing_subscriber::fmt` write directly to stdout which mangles the input line while
+/// the repl is in raw mode. Pass this layer the [`Sender`] returned by [`Repl::spawn`] to have events printed
+/// through the repl instead, or the sender of a dedicated channel whose receiver is polled by your own read
+/// eval print loop, printing each message via [`Repl::log`].
+///
+/// Each event's level is mapped to a [`Level`], its target is used as the source, and every field other than
+/// the message is appended as `name=value`. Events are discarded when the channel is full or closed, as waiting
+/// for capacity would block the thread that emitted the event.
+///
+/// Events below the repl's minimum log level are discarded before they are formatted, so they never take up
+/// capacity of the channel. The level is read from the [`SharedLevel`] returned by [`Repl::shared_log_level`] so
+/// changes made via [`Repl::set_log_level`] apply immediately. Only this layer discards these events, other
+/// layers of the same subscriber still receive them.
+///
+/// # Example
+///
+/// ```ignore
+/// use tracing_subscriber::prelude::*;
+///
+/// let log_level = repl.shared_log_level();
+/// let (repl_task, message_sender) = repl.spawn(ctx);
+///
+/// tracing_subscriber::registry()
+///     .with(ReplTracingLayer::new(message_sender.clone(), log_level))
+///     .init();
+/// ```
+///
+/// [`Layer`]: <https://docs.rs/tracing-subscriber/latest/tracing_subscriber/layer/trait.Layer.html>
+/// [`Sender`]: <https://docs.rs/tokio/latest/tokio/sync/mpsc/struct.Sender.html>
+/// [`Repl::spawn`]: crate::line::Repl::spawn
+/// [`Repl::log`]: crate::line::Repl::log
+/// [`Repl::set_log_level`]: crate::line::Repl::set_log_level
+/// [`Repl::shared_log_level`]: crate::line::Repl::shared_log_level
+pub struct ReplTracingLayer<M> {
+    sender: Sender<M>,
+    level: SharedLevel,
+}
+
+impl<M> ReplTracingLayer<M>
+where
+    M: From<LogMessage> + Send + 'static,
+{
+    /// Creates a new layer that sends every event at or above the given `level` into the channel of the given
+    /// `sender`
+    pub fn new(sender: Sender<M>, level: SharedLevel) -> Self {
+        ReplTracingLayer { sender, level }
+    }
+}
+
+fn to_level(level: tracing::Level) -> Level {
+    match level {
+        tracing::Level::TRACE => Level::Trace,
+        tracing::Level::DEBUG => Level::Debug,
+        tracing::Level::INFO => Level::Info,
+        tracing::Level::WARN => Level::Warn,
+        tracing::Level::ERROR => Level::Error,
+    }
+}
+
+/// Collects the message and remaining fields of an event
+#[derive(Default)]
+struct EventVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for EventVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.record_debug(field, &value)
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+}
+
+impl<S, M> Layer<S> for ReplTracingLayer<M>
+where
+    S: Subscriber,
+    M: From<LogMessage> + Send + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let level = to_level(*metadata.level());
+        // Filtering in `Layer::enabled` would disable the event for every layer of the subscriber
+        if !self.level.enabled(level) {
+            return;
+        }
+
+        let mut visitor = EventVisitor::default();
+        event.record(&mut visitor);
+        visitor.message.push_str(&visitor.fields);
+
+        let message = LogMessage::new(level, visitor.message.trim_start())
+            .with_source(metadata.target())
+            .with_timestamp();
+        let _ = self.sender.try_send(M::from(message));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use tokio::sync::mpsc::channel;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Counts every event it receives
+    struct CountingLayer(Arc<AtomicUsize>);
+
+    impl<S: Subscriber> Layer<S> for CountingLayer {
+        fn on_event(&self, _event: &Event<'_>, _ctx: Context<'_, S>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn events_below_the_shared_level_are_not_sent() {
+        let (sender, mut receiver) = channel::<LogMessage>(8);
+        let level = SharedLevel::new(Level::Info);
+        let subscriber =
+            tracing_subscriber::registry().with(ReplTracingLayer::new(sender, level.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..2 {
+                tracing::debug!("debug");
+                tracing::info!(count = 1, "info");
+                level.set(Level::Debug);
+            }
+        });
+
+        let received = std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|message| (message.level(), String::from(message.message())))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            received,
+            [
+                (Level::Info, String::from("info count=1")),
+                (Level::Debug, String::from("debug")),
+                (Level::Info, String::from("info count=1")),
+            ]
+        );
+    }
+
+    #[test]
+    fn sibling_layers_receive_events_below_the_shared_level() {
+        let (sender, mut receiver) = channel::<LogMessage>(8);
+        let count = Arc::new(AtomicUsize::new(0));
+        let subscriber = tracing_subscriber::registry()
+            .with(ReplTracingLayer::new(sender, SharedLevel::new(Level::Warn)))
+            .with(CountingLayer(Arc::clone(&count)));
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!("debug");
+            tracing::info!("info");
+            tracing::warn!("warn");
+        });
+
+        assert_eq!(count.load(Ordering::Relaxed), 3);
+        let received = std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|message| message.level())
+            .collect::<Vec<_>>();
+        assert_eq!(received, [Level::Warn]);
+    }
+}